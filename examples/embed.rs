@@ -0,0 +1,24 @@
+use anyhow::Result;
+use arrow::util::pretty::pretty_format_batches;
+use futures::TryStreamExt;
+use taotie::Taotie;
+
+// cargo run --example embed -- <file>
+#[tokio::main]
+async fn main() -> Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .expect("usage: embed <csv, ndjson or parquet file>");
+
+    let mut taotie = Taotie::new();
+    taotie.connect("data", &path).await?;
+    println!("{:?}", taotie.schema("data").await?);
+
+    let batches = taotie.describe("data").await?;
+    println!("{}", pretty_format_batches(&batches)?);
+
+    let stream = taotie.sql("SELECT count(*) AS rows FROM data").await?;
+    let batches: Vec<_> = stream.try_collect().await?;
+    println!("{}", pretty_format_batches(&batches)?);
+    Ok(())
+}
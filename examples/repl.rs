@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use clap::{arg, command, Parser, Subcommand};
+use clap::{Parser, Subcommand};
 use reedline_repl_rs::clap::{ArgAction, ArgMatches};
 use reedline_repl_rs::{CallBackMap, Repl, Result};
 
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+
+use crate::{utils::human_bytes, DatasetConn};
+
+#[derive(Debug, Clone)]
+pub enum DatasetSource {
+    // registered via `connect`
+    File(DatasetConn),
+    // registered via `let <name> = <sql>`, evaluated lazily
+    View(String),
+    // registered via `cache <name> = <sql>`, materialised in memory
+    Memory {
+        sql: String,
+        rows: usize,
+        size: usize,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct DatasetInfo {
+    pub name: String,
+    pub kind: String,
+    pub source: Option<String>,
+    pub format: Option<String>,
+    pub compression: Option<String>,
+    pub files: Option<u64>,
+    pub size: Option<u64>,
+    pub rows: Option<u64>,
+    pub memory: Option<usize>,
+    // why the details of the dataset could not be read, e.g. a file was removed
+    pub error: Option<String>,
+}
+
+impl DatasetSource {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DatasetSource::File(_) => "file",
+            DatasetSource::View(_) => "view",
+            DatasetSource::Memory { .. } => "memory",
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        match self {
+            DatasetSource::File(conn) => conn.location(),
+            DatasetSource::View(sql) | DatasetSource::Memory { sql, .. } => sql,
+        }
+    }
+}
+
+impl DatasetInfo {
+    pub fn new(name: impl Into<String>, kind: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind: kind.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_record_batch(infos: &[DatasetInfo]) -> anyhow::Result<RecordBatch> {
+        let strings = |f: fn(&DatasetInfo) -> Option<String>| {
+            Arc::new(StringArray::from(infos.iter().map(f).collect::<Vec<_>>())) as ArrayRef
+        };
+        let numbers = |f: fn(&DatasetInfo) -> Option<u64>| {
+            Arc::new(UInt64Array::from(infos.iter().map(f).collect::<Vec<_>>())) as ArrayRef
+        };
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("name", strings(|i| Some(i.name.clone()))),
+            ("kind", strings(|i| Some(i.kind.clone()))),
+            ("source", strings(|i| i.source.clone())),
+            ("format", strings(|i| i.format.clone())),
+            ("compression", strings(|i| i.compression.clone())),
+            ("files", numbers(|i| i.files)),
+            ("size", strings(|i| i.size.map(human_bytes))),
+            ("rows", numbers(|i| i.rows)),
+            (
+                "memory",
+                strings(|i| i.memory.map(|v| human_bytes(v as u64))),
+            ),
+            ("error", strings(|i| i.error.clone())),
+        ])?;
+        Ok(batch)
+    }
+}
@@ -0,0 +1,209 @@
+use std::{fmt, fs, path::Path, sync::Arc};
+
+use arrow::array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+use serde::Deserialize;
+
+use super::{format_batches, OutputOptions};
+use crate::{utils::quote_literal, ReplDisplay};
+
+/// An expectation about a dataset, as written in a rules file:
+///
+/// ```yaml
+/// - not_null: id
+/// - range: { column: age, min: 0, max: 150 }
+/// - foreign_key: { column: user_id, dataset: users, references: id }
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    NotNull(String),
+    Unique(String),
+    Range {
+        column: String,
+        min: Option<Value>,
+        max: Option<Value>,
+    },
+    Regex {
+        column: String,
+        pattern: String,
+    },
+    AllowedValues {
+        column: String,
+        values: Vec<Value>,
+    },
+    RowCount {
+        min: Option<u64>,
+        max: Option<u64>,
+    },
+    ForeignKey {
+        column: String,
+        dataset: String,
+        // defaults to the same column name in the referenced dataset
+        references: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug)]
+pub struct RuleResult {
+    pub rule: Rule,
+    // the number of offending rows, or of duplicated values for `unique`
+    pub failures: u64,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct CheckReport(Vec<RuleResult>);
+
+impl Rule {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Vec<Rule>> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    // each rule is a map of one key, rather than the YAML tags serde_yaml uses for enums
+    pub fn parse(content: &str) -> anyhow::Result<Vec<Rule>> {
+        let deserializer = serde_yaml::Deserializer::from_str(content);
+        Ok(serde_yaml::with::singleton_map_recursive::deserialize(
+            deserializer,
+        )?)
+    }
+}
+
+impl Value {
+    pub fn to_sql(&self) -> String {
+        match self {
+            Value::Number(v) => v.to_string(),
+            Value::Text(v) => quote_literal(v),
+        }
+    }
+}
+
+impl CheckReport {
+    pub fn new(results: Vec<RuleResult>) -> Self {
+        Self(results)
+    }
+
+    pub fn results(&self) -> &[RuleResult] {
+        &self.0
+    }
+
+    pub fn failed(&self) -> usize {
+        self.0.iter().filter(|r| r.failures > 0).count()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bound = |v: &Option<Value>| v.as_ref().map_or("-".to_string(), |v| v.to_sql());
+        match self {
+            Rule::NotNull(column) => write!(f, "not_null({})", column),
+            Rule::Unique(column) => write!(f, "unique({})", column),
+            Rule::Range { column, min, max } => {
+                write!(f, "range({}, {}, {})", column, bound(min), bound(max))
+            }
+            Rule::Regex { column, pattern } => {
+                write!(f, "regex({}, {})", column, quote_literal(pattern))
+            }
+            Rule::AllowedValues { column, values } => {
+                let values: Vec<_> = values.iter().map(|v| v.to_sql()).collect();
+                write!(f, "allowed_values({}, [{}])", column, values.join(", "))
+            }
+            Rule::RowCount { min, max } => {
+                let bound = |v: &Option<u64>| v.map_or("-".to_string(), |v| v.to_string());
+                write!(f, "row_count({}, {})", bound(min), bound(max))
+            }
+            Rule::ForeignKey {
+                column,
+                dataset,
+                references,
+            } => write!(
+                f,
+                "foreign_key({}, {}.{})",
+                column,
+                dataset,
+                references.as_deref().unwrap_or(column)
+            ),
+        }
+    }
+}
+
+impl ReplDisplay for CheckReport {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "rule",
+                Arc::new(StringArray::from_iter_values(
+                    self.0.iter().map(|r| r.rule.to_string()),
+                )) as ArrayRef,
+            ),
+            (
+                "status",
+                Arc::new(StringArray::from_iter_values(self.0.iter().map(|r| {
+                    if r.failures > 0 {
+                        "FAIL"
+                    } else {
+                        "PASS"
+                    }
+                }))) as ArrayRef,
+            ),
+            (
+                "failures",
+                Arc::new(UInt64Array::from_iter_values(
+                    self.0.iter().map(|r| r.failures),
+                )) as ArrayRef,
+            ),
+        ])?;
+
+        let mut output = format_batches(&[batch], &output)?;
+        for result in self.0.iter().filter(|r| r.failures > 0) {
+            if let Some(detail) = &result.detail {
+                output.push_str(&format!("\n\n{}:\n{}", result.rule, detail));
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = Rule::parse(
+            r#"
+- not_null: id
+- unique: id
+- range: { column: age, min: 0, max: 150 }
+- regex: { column: email, pattern: "^[^@]+@[^@]+$" }
+- allowed_values: { column: status, values: [active, "it's"] }
+- row_count: { min: 1 }
+- foreign_key: { column: user_id, dataset: users, references: id }
+"#,
+        )
+        .unwrap();
+        let rules: Vec<_> = rules.iter().map(|r| r.to_string()).collect();
+        assert_eq!(
+            rules,
+            vec![
+                "not_null(id)",
+                "unique(id)",
+                "range(age, 0, 150)",
+                "regex(email, '^[^@]+@[^@]+$')",
+                "allowed_values(status, ['active', 'it''s'])",
+                "row_count(1, -)",
+                "foreign_key(user_id, users.id)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use parquet::{
+    basic::Compression,
+    file::{
+        metadata::ParquetMetaData, reader::FileReader, serialized_reader::SerializedFileReader,
+    },
+};
+
+/// List the data files behind a dataset location, which could be a single file or a directory.
+pub(crate) fn list_files(location: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let location = location.as_ref();
+    if location.is_file() {
+        return Ok(vec![location.to_path_buf()]);
+    }
+
+    let mut files = vec![];
+    for entry in fs::read_dir(location)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.starts_with('.') || name.starts_with('_'))
+            .unwrap_or(false);
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(list_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+pub(crate) fn disk_usage(files: &[PathBuf]) -> io::Result<u64> {
+    files
+        .iter()
+        .map(|file| fs::metadata(file).map(|m| m.len()))
+        .sum()
+}
+
+pub(crate) fn parquet_metadata(file: impl AsRef<Path>) -> anyhow::Result<ParquetMetaData> {
+    let reader = SerializedFileReader::new(File::open(file)?)?;
+    Ok(reader.metadata().clone())
+}
+
+pub(crate) fn codec_name(compression: Compression) -> &'static str {
+    match compression {
+        Compression::UNCOMPRESSED => "uncompressed",
+        Compression::SNAPPY => "snappy",
+        Compression::GZIP(_) => "gzip",
+        Compression::LZO => "lzo",
+        Compression::BROTLI(_) => "brotli",
+        Compression::LZ4 => "lz4",
+        Compression::ZSTD(_) => "zstd",
+        Compression::LZ4_RAW => "lz4_raw",
+    }
+}
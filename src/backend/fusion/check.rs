@@ -0,0 +1,138 @@
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::prelude::SessionContext;
+
+use crate::{
+    backend::{Rule, RuleResult},
+    utils::{quote_ident, quote_literal},
+};
+
+pub(super) const CHECK_TABLE: &str = "__taotie_check";
+pub(super) const CHECK_REF_TABLE: &str = "__taotie_check_ref";
+const SAMPLE_ROWS: usize = 5;
+
+/// Evaluate a rule against the dataset registered as `CHECK_TABLE`, and the referenced dataset
+/// registered as `CHECK_REF_TABLE` for foreign keys.
+pub(super) async fn check_rule(ctx: &SessionContext, rule: &Rule) -> anyhow::Result<RuleResult> {
+    if let Rule::RowCount { min, max } = rule {
+        let rows = ctx.table(CHECK_TABLE).await?.count().await? as u64;
+        let passed = !min.is_some_and(|min| rows < min) && !max.is_some_and(|max| rows > max);
+        return Ok(RuleResult {
+            rule: rule.clone(),
+            failures: if passed { 0 } else { 1 },
+            detail: Some(format!("found {} rows", rows)),
+        });
+    }
+
+    let df = ctx.sql(&violations_sql(rule)).await?;
+    let failures = df.clone().count().await? as u64;
+    let detail = match failures {
+        0 => None,
+        _ => {
+            let batches = df.limit(0, Some(SAMPLE_ROWS))?.collect().await?;
+            Some(pretty_format_batches(&batches)?.to_string())
+        }
+    };
+    Ok(RuleResult {
+        rule: rule.clone(),
+        failures,
+        detail,
+    })
+}
+
+// a query returning the offending rows of a rule
+fn violations_sql(rule: &Rule) -> String {
+    let filter = match rule {
+        Rule::NotNull(column) => format!("{} IS NULL", quote_ident(column)),
+        Rule::Unique(column) => {
+            let column = quote_ident(column);
+            return format!(
+                "SELECT {0}, count(*) AS count FROM {1} WHERE {0} IS NOT NULL GROUP BY {0} HAVING count(*) > 1 ORDER BY count DESC",
+                column, CHECK_TABLE
+            );
+        }
+        Rule::Range { column, min, max } => {
+            let column = quote_ident(column);
+            let mut conditions = vec![];
+            if let Some(min) = min {
+                conditions.push(format!("{} < {}", column, min.to_sql()));
+            }
+            if let Some(max) = max {
+                conditions.push(format!("{} > {}", column, max.to_sql()));
+            }
+            if conditions.is_empty() {
+                "false".to_string()
+            } else {
+                conditions.join(" OR ")
+            }
+        }
+        Rule::Regex { column, pattern } => {
+            let column = quote_ident(column);
+            format!(
+                "{0} IS NOT NULL AND CAST({0} AS VARCHAR) !~ {1}",
+                column,
+                quote_literal(pattern)
+            )
+        }
+        Rule::AllowedValues { column, values } => {
+            let values: Vec<_> = values.iter().map(|v| v.to_sql()).collect();
+            format!(
+                "{0} IS NOT NULL AND {0} NOT IN ({1})",
+                quote_ident(column),
+                values.join(", ")
+            )
+        }
+        Rule::ForeignKey {
+            column, references, ..
+        } => {
+            let column = quote_ident(column);
+            let references = references.as_deref().map(quote_ident);
+            format!(
+                "{1}.{0} IS NOT NULL AND NOT EXISTS (SELECT 1 FROM {2} WHERE {2}.{3} = {1}.{0})",
+                column,
+                CHECK_TABLE,
+                CHECK_REF_TABLE,
+                references.as_deref().unwrap_or(&column)
+            )
+        }
+        Rule::RowCount { .. } => unreachable!("row_count is checked without a query"),
+    };
+    format!("SELECT * FROM {} WHERE {}", CHECK_TABLE, filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Value;
+
+    #[test]
+    fn test_violations_sql() {
+        let rule = Rule::Range {
+            column: "age".into(),
+            min: Some(Value::Number(0.0)),
+            max: None,
+        };
+        assert_eq!(
+            violations_sql(&rule),
+            "SELECT * FROM __taotie_check WHERE \"age\" < 0"
+        );
+
+        let rule = Rule::AllowedValues {
+            column: "status".into(),
+            values: vec![Value::Text("a".into()), Value::Number(1.5)],
+        };
+        assert_eq!(
+            violations_sql(&rule),
+            "SELECT * FROM __taotie_check WHERE \"status\" IS NOT NULL AND \"status\" NOT IN ('a', 1.5)"
+        );
+
+        let rule = Rule::ForeignKey {
+            column: "user_id".into(),
+            dataset: "users".into(),
+            references: Some("id".into()),
+        };
+        assert_eq!(
+            violations_sql(&rule),
+            "SELECT * FROM __taotie_check WHERE __taotie_check.\"user_id\" IS NOT NULL AND NOT EXISTS (SELECT 1 FROM __taotie_check_ref WHERE __taotie_check_ref.\"id\" = __taotie_check.\"user_id\")"
+        );
+    }
+}
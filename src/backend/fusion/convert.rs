@@ -0,0 +1,124 @@
+use arrow::{array::AsArray, datatypes::UInt64Type};
+use datafusion::{
+    config::{CsvOptions, JsonOptions, TableParquetOptions},
+    dataframe::DataFrameWriteOptions,
+    prelude::DataFrame,
+};
+
+use crate::{ConvertOpts, DatasetConn};
+
+use super::sorted;
+
+pub(super) async fn write_dataframe(df: DataFrame, opts: &ConvertOpts) -> anyhow::Result<u64> {
+    let df = sorted(df, &opts.sort_by)?;
+
+    let write_opts = DataFrameWriteOptions::new()
+        .with_single_file_output(opts.partition_by.is_empty())
+        .with_partition_by(opts.partition_by.clone());
+
+    let batches = match &opts.dst {
+        DatasetConn::Parquet(filename) => {
+            let mut parquet_opts = TableParquetOptions::default();
+            if let Some(size) = opts.row_group_size {
+                parquet_opts.global.max_row_group_size = size;
+            }
+            if let Some(codec) = opts.codec {
+                parquet_opts.global.compression = Some(codec.as_compression_str().to_string());
+            }
+            df.write_parquet(filename, write_opts, Some(parquet_opts))
+                .await?
+        }
+        DatasetConn::Csv(file_opts) => {
+            let csv_opts = CsvOptions {
+                compression: *file_opts.compression.get_variant(),
+                ..Default::default()
+            };
+            df.write_csv(&file_opts.filename, write_opts, Some(csv_opts))
+                .await?
+        }
+        DatasetConn::NdJson(file_opts) => {
+            let json_opts = JsonOptions {
+                compression: *file_opts.compression.get_variant(),
+                ..Default::default()
+            };
+            df.write_json(&file_opts.filename, write_opts, Some(json_opts))
+                .await?
+        }
+        DatasetConn::Postgres(_) => anyhow::bail!("Postgres is not supported as a destination"),
+    };
+
+    let rows = batches
+        .iter()
+        .map(|batch| {
+            batch
+                .column(0)
+                .as_primitive::<UInt64Type>()
+                .iter()
+                .flatten()
+                .sum::<u64>()
+        })
+        .sum();
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::Int64Type;
+    use clap::Parser;
+    use parquet::basic::Compression;
+
+    use super::*;
+    use crate::{
+        backend::{files::parquet_metadata, DataFusionBackend},
+        test_utils::{connect_opts, TestDir},
+        Backend,
+    };
+
+    #[tokio::test]
+    async fn test_csv_to_parquet() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let csv = dir.write("t.csv", "id,amount\n1,30\n2,10\n3,50\n4,20\n5,40\n");
+        let dst = dir.path("t.parquet");
+        let mut backend = DataFusionBackend::new();
+        backend.connect(&connect_opts("t", &csv)).await?;
+
+        let args = [
+            "convert",
+            "t",
+            &dst,
+            "--sort-by",
+            "amount:desc",
+            "--codec",
+            "zstd",
+            "--row-group-size",
+            "2",
+        ];
+        let opts = ConvertOpts::try_parse_from(args)?;
+        assert_eq!(backend.convert(&opts).await?, 5);
+
+        let metadata = parquet_metadata(&dst)?;
+        assert_eq!(metadata.file_metadata().num_rows(), 5);
+        let sizes: Vec<_> = metadata.row_groups().iter().map(|g| g.num_rows()).collect();
+        assert_eq!(sizes, [2, 2, 1]);
+        // the level isn't stored in the file
+        let codec = metadata.row_group(0).column(0).compression();
+        assert!(matches!(codec, Compression::ZSTD(_)));
+
+        backend.connect(&connect_opts("p", &dst)).await?;
+        let batch = backend.collect("select id from p").await?;
+        let ids: Vec<_> = batch
+            .column(0)
+            .as_primitive::<Int64Type>()
+            .values()
+            .to_vec();
+        assert_eq!(ids, [3, 5, 1, 4, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_options_need_parquet_output() {
+        let args = ["convert", "t", "t.csv", "--codec", "zstd"];
+        let opts = ConvertOpts::try_parse_from(args).unwrap();
+        assert!(opts.verify().is_err());
+    }
+}
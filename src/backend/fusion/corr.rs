@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray, Float64Array, RecordBatch, StringArray},
+    datatypes::{DataType, Float64Type},
+};
+use datafusion::prelude::{DataFrame, SessionContext};
+
+use crate::{cli::CorrMethod, utils::quote_ident};
+
+const CORR_TABLE: &str = "__taotie_corr";
+
+pub(super) async fn correlation(
+    ctx: &SessionContext,
+    df: DataFrame,
+    columns: &[String],
+    method: CorrMethod,
+) -> anyhow::Result<RecordBatch> {
+    let numeric: Vec<String> = df
+        .schema()
+        .fields()
+        .iter()
+        .filter(|f| f.data_type().is_numeric())
+        .map(|f| f.name().clone())
+        .collect();
+    let columns = if columns.is_empty() {
+        numeric
+    } else {
+        if let Some(c) = columns.iter().find(|c| !numeric.contains(c)) {
+            anyhow::bail!("{} is not a numeric column", c);
+        }
+        columns.to_vec()
+    };
+    if columns.len() < 2 {
+        anyhow::bail!("at least two numeric columns are needed");
+    }
+
+    ctx.register_table(CORR_TABLE, df.into_view())?;
+    let ret = pairwise_corr(ctx, &columns, method).await;
+    ctx.deregister_table(CORR_TABLE)?;
+    let values = ret?;
+
+    let n = columns.len();
+    let mut arrays = vec![(
+        "column".to_string(),
+        Arc::new(StringArray::from(columns.clone())) as ArrayRef,
+    )];
+    for (j, name) in columns.iter().enumerate() {
+        let array = Float64Array::from_iter((0..n).map(|i| match i.cmp(&j) {
+            std::cmp::Ordering::Equal => Some(1.0),
+            std::cmp::Ordering::Less => values[pair_index(n, i, j)],
+            std::cmp::Ordering::Greater => values[pair_index(n, j, i)],
+        }));
+        arrays.push((name.clone(), Arc::new(array) as ArrayRef));
+    }
+    Ok(RecordBatch::try_from_iter(arrays)?)
+}
+
+// position of the pair (i, j), i < j, in the upper triangle of the matrix
+fn pair_index(n: usize, i: usize, j: usize) -> usize {
+    i * (2 * n - i - 1) / 2 + (j - i - 1)
+}
+
+async fn pairwise_corr(
+    ctx: &SessionContext,
+    columns: &[String],
+    method: CorrMethod,
+) -> anyhow::Result<Vec<Option<f64>>> {
+    let source = match method {
+        CorrMethod::Pearson => CORR_TABLE.to_string(),
+        // spearman is pearson over the ranks, ties get the average of their ranks
+        CorrMethod::Spearman => {
+            let ranks = columns
+                .iter()
+                .map(|c| {
+                    let c = quote_ident(c);
+                    format!(
+                        "CASE WHEN {c} IS NULL THEN NULL ELSE rank() OVER (ORDER BY {c}) + (count(*) OVER (PARTITION BY {c}) - 1) / 2.0 END AS {c}"
+                    )
+                })
+                .collect::<Vec<_>>();
+            format!("(SELECT {} FROM {})", ranks.join(", "), CORR_TABLE)
+        }
+    };
+
+    let pairs: Vec<_> = columns
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| {
+            columns[i + 1..].iter().map(move |b| {
+                format!(
+                    "corr(CAST({} AS DOUBLE), CAST({} AS DOUBLE))",
+                    quote_ident(a),
+                    quote_ident(b)
+                )
+            })
+        })
+        .collect();
+    let sql = format!("SELECT {} FROM {}", pairs.join(", "), source);
+    let batches = ctx.sql(&sql).await?.collect().await?;
+    let batch = batches
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("no correlation computed"))?;
+
+    let values = batch
+        .columns()
+        .iter()
+        .map(|array| {
+            let array = arrow::compute::cast(array, &DataType::Float64)?;
+            let array = array.as_primitive::<Float64Type>();
+            Ok((!array.is_null(0)).then(|| array.value(0)))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pair_index() {
+        let n = 4;
+        let mut expected = 0;
+        for i in 0..n {
+            for j in i + 1..n {
+                assert_eq!(pair_index(n, i, j), expected);
+                expected += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{ArrayRef, AsArray, Float64Array, Int64Array, RecordBatch, StringArray},
+    datatypes::{DataType, Int64Type},
+};
+use datafusion::{
+    functions_aggregate::{count::count, sum::sum},
+    prelude::{cast, col, ident, lit, DataFrame, Expr},
+};
+
+// a reserved name for the count of each group, as the columns may include a `count`
+const COUNT: &str = "__taotie_count";
+
+pub(super) async fn value_counts(
+    df: DataFrame,
+    columns: &[String],
+    top: usize,
+    normalize: bool,
+) -> anyhow::Result<RecordBatch> {
+    let groups: Vec<Expr> = columns.iter().map(ident).collect();
+    let grouped = df.aggregate(groups, vec![count(lit(1)).alias(COUNT)])?;
+
+    let totals = grouped
+        .clone()
+        .aggregate(
+            vec![],
+            vec![
+                sum(col(COUNT)).alias("total"),
+                count(lit(1)).alias("groups"),
+            ],
+        )?
+        .collect()
+        .await?;
+    let (total, distinct) = match totals.first() {
+        Some(batch) if batch.num_rows() > 0 => (
+            batch.column(0).as_primitive::<Int64Type>().value(0),
+            batch.column(1).as_primitive::<Int64Type>().value(0),
+        ),
+        _ => (0, 0),
+    };
+
+    let mut order = vec![col(COUNT).sort(false, false)];
+    order.extend(columns.iter().map(|c| ident(c).sort(true, false)));
+    let mut select: Vec<Expr> = columns
+        .iter()
+        .map(|c| cast(ident(c), DataType::Utf8).alias(c))
+        .collect();
+    select.push(col(COUNT));
+    let batches = grouped
+        .sort(order)?
+        .limit(0, Some(top))?
+        .select(select)?
+        .collect()
+        .await?;
+
+    let mut values: Vec<Vec<Option<String>>> = vec![vec![]; columns.len()];
+    let mut counts = vec![];
+    for batch in batches.iter() {
+        for (i, column) in values.iter_mut().enumerate() {
+            column.extend(
+                batch
+                    .column(i)
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|v| v.map(|v| v.to_string())),
+            );
+        }
+        counts.extend(
+            batch
+                .column(columns.len())
+                .as_primitive::<Int64Type>()
+                .values()
+                .iter(),
+        );
+    }
+
+    // fold the tail into a single bucket
+    let rest = distinct - counts.len() as i64;
+    if rest > 0 {
+        for (i, column) in values.iter_mut().enumerate() {
+            column.push((i == 0).then(|| format!("(other {} values)", rest)));
+        }
+        counts.push(total - counts.iter().sum::<i64>());
+    }
+
+    let ratio = |c: &i64| {
+        if total == 0 {
+            0.0
+        } else {
+            *c as f64 / total as f64
+        }
+    };
+    let mut arrays: Vec<(String, ArrayRef)> = columns
+        .iter()
+        .cloned()
+        .zip(
+            values
+                .into_iter()
+                .map(|v| Arc::new(StringArray::from(v)) as ArrayRef),
+        )
+        .collect();
+    if normalize {
+        arrays.push((
+            output_name("fraction", columns),
+            Arc::new(Float64Array::from_iter_values(counts.iter().map(ratio))),
+        ));
+    } else {
+        let percentages = counts.iter().map(|c| (ratio(c) * 10000.0).round() / 100.0);
+        arrays.push((
+            output_name("count", columns),
+            Arc::new(Int64Array::from(counts.clone())),
+        ));
+        arrays.push((
+            output_name("percentage", columns),
+            Arc::new(Float64Array::from_iter_values(percentages)),
+        ));
+    }
+
+    Ok(RecordBatch::try_from_iter(arrays)?)
+}
+
+// a computed column takes trailing `_` until its name differs from the counted columns
+fn output_name(name: &str, columns: &[String]) -> String {
+    let mut name = name.to_string();
+    while columns.contains(&name) {
+        name.push('_');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::util::pretty::pretty_format_batches;
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_count_column() -> anyhow::Result<()> {
+        let ctx = SessionContext::new();
+        let df = ctx
+            .sql("select * from (values (1), (1), (2)) as t(count)")
+            .await?;
+        let batch = value_counts(df, &["count".to_string()], 10, false).await?;
+        assert_eq!(
+            pretty_format_batches(&[batch])?.to_string(),
+            "+-------+--------+------------+\n\
+             | count | count_ | percentage |\n\
+             +-------+--------+------------+\n\
+             | 1     | 2      | 66.67      |\n\
+             | 2     | 1      | 33.33      |\n\
+             +-------+--------+------------+"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_other_values() -> anyhow::Result<()> {
+        let ctx = SessionContext::new();
+        let df = ctx
+            .sql("select * from (values ('a'), ('a'), ('a'), ('b'), ('b'), ('c'), ('d')) as t(v)")
+            .await?;
+        let batch = value_counts(df, &["v".to_string()], 2, false).await?;
+        assert_eq!(
+            pretty_format_batches(&[batch])?.to_string(),
+            "+------------------+-------+------------+\n\
+             | v                | count | percentage |\n\
+             +------------------+-------+------------+\n\
+             | a                | 3     | 42.86      |\n\
+             | b                | 2     | 28.57      |\n\
+             | (other 2 values) | 2     | 28.57      |\n\
+             +------------------+-------+------------+"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_normalize_combinations() -> anyhow::Result<()> {
+        let ctx = SessionContext::new();
+        let df = ctx
+            .sql("select * from (values ('x', 1), ('x', 1), ('x', 2), (null, 1)) as t(a, b)")
+            .await?;
+        let columns = ["a".to_string(), "b".to_string()];
+        let batch = value_counts(df, &columns, 10, true).await?;
+        assert_eq!(
+            pretty_format_batches(&[batch])?.to_string(),
+            "+---+---+----------+\n\
+             | a | b | fraction |\n\
+             +---+---+----------+\n\
+             | x | 1 | 0.5      |\n\
+             | x | 2 | 0.25     |\n\
+             |   | 1 | 0.25     |\n\
+             +---+---+----------+"
+        );
+        Ok(())
+    }
+}
@@ -1,11 +1,19 @@
 use std::{fmt, sync::Arc};
 
-use arrow::datatypes::{DataType, Field};
+use arrow::{
+    array::RecordBatch,
+    datatypes::{DataType, Field},
+};
 use datafusion::{
     functions_aggregate::{count::count, expr_fn::avg, median::median, stddev::stddev, sum::sum},
     prelude::{array_length, case, cast, col, is_null, length, lit, max, min, DataFrame},
 };
 
+use crate::{
+    backend::{format_batches, OutputOptions},
+    ReplDisplay,
+};
+
 #[allow(unused)]
 #[derive(Debug)]
 pub enum DescribeMethod {
@@ -115,6 +123,22 @@ impl DataFrameDescriber {
     }
 }
 
+#[derive(Debug)]
+pub struct Description {
+    pub stats: DataFrame,
+    pub sparklines: Option<RecordBatch>,
+}
+
+impl ReplDisplay for Description {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let stats = self.stats.display(output).await?;
+        match self.sparklines {
+            Some(batch) => Ok(format!("{}\n{}", stats, format_batches(&[batch], &output)?)),
+            None => Ok(stats),
+        }
+    }
+}
+
 impl fmt::Display for DescribeMethod {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -71,7 +71,7 @@ impl DescribeDataFrame {
         let original_schema_fields = self.df.schema().fields().iter();
 
         // 使用所有列分别计算出来的指标，这里是两行数据
-        let batches = vec![self.count(), self.null_count()];
+        let batches = [self.count(), self.null_count()];
 
         // 指标名这一列
         let mut describe_col_vec: Vec<ArrayRef> = vec![Arc::new(StringArray::from(
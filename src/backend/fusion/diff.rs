@@ -0,0 +1,223 @@
+use arrow::datatypes::DataType;
+use datafusion::{
+    common::JoinType,
+    functions_aggregate::{count::count, sum::sum},
+    logical_expr::{binary_expr, Operator},
+    prelude::{cast, coalesce, col, concat_ws, ident, lit, when, DataFrame, Expr},
+};
+
+use crate::{
+    backend::{format_batches, OutputOptions},
+    ReplDisplay,
+};
+
+const STATUS: &str = "_status";
+const CHANGED_COLUMNS: &str = "_changed_columns";
+// always true on the side a row comes from, null on the other side of the full join
+const IN_A: &str = "__taotie_in_a";
+const IN_B: &str = "__taotie_in_b";
+
+pub(super) struct DataDiff {
+    // one row per key that is only in a, only in b or has changed values
+    pub diff: DataFrame,
+    sections: Vec<(String, DataFrame)>,
+}
+
+impl DataDiff {
+    pub async fn try_new(
+        a: DataFrame,
+        b: DataFrame,
+        keys: &[String],
+        sample: usize,
+    ) -> anyhow::Result<Self> {
+        let (a_schema, b_schema) = (a.schema().as_arrow().clone(), b.schema().as_arrow().clone());
+        for key in keys {
+            if a_schema.field_with_name(key).is_err() || b_schema.field_with_name(key).is_err() {
+                anyhow::bail!("key column {} must exist in both datasets", key);
+            }
+        }
+        // compare the columns that both datasets have, cast to string if the types differ
+        let values: Vec<(String, bool)> = a_schema
+            .fields()
+            .iter()
+            .filter(|f| !keys.contains(f.name()))
+            .filter_map(|f| {
+                let fb = b_schema.field_with_name(f.name()).ok()?;
+                Some((f.name().clone(), fb.data_type() != f.data_type()))
+            })
+            .collect();
+
+        let a = prefixed(a, "a", IN_A)?;
+        let b = prefixed(b, "b", IN_B)?;
+        let a_key_names: Vec<_> = keys.iter().map(|k| format!("a_{}", k)).collect();
+        let b_key_names: Vec<_> = keys.iter().map(|k| format!("b_{}", k)).collect();
+        let a_keys: Vec<_> = a_key_names.iter().map(|k| k.as_str()).collect();
+        let b_keys: Vec<_> = b_key_names.iter().map(|k| k.as_str()).collect();
+        let joined = a.join(b, JoinType::Full, &a_keys, &b_keys, None)?;
+
+        let any_changed = values
+            .iter()
+            .map(|(c, as_string)| distinct(c, *as_string))
+            .reduce(|acc, e| acc.or(e))
+            .unwrap_or(lit(false));
+        let status = when(col(IN_B).is_null(), lit("only_in_a"))
+            .when(col(IN_A).is_null(), lit("only_in_b"))
+            .when(any_changed, lit("changed"))
+            .otherwise(lit("same"))?;
+        let changed_columns = values
+            .iter()
+            .map(|(c, as_string)| when(distinct(c, *as_string), lit(c.as_str())).end())
+            .collect::<Result<Vec<_>, _>>()?;
+        let changed_columns = if changed_columns.is_empty() {
+            lit("")
+        } else {
+            // a row of one side only has no values to compare
+            when(
+                col(IN_A).is_not_null().and(col(IN_B).is_not_null()),
+                concat_ws(lit(","), changed_columns),
+            )
+            .otherwise(lit(""))?
+        };
+
+        let mut exprs: Vec<Expr> = keys
+            .iter()
+            .map(|k| coalesce(vec![ident(format!("a_{}", k)), ident(format!("b_{}", k))]).alias(k))
+            .collect();
+        exprs.push(status.alias(STATUS));
+        exprs.push(changed_columns.alias(CHANGED_COLUMNS));
+        for (c, _) in values.iter() {
+            exprs.push(ident(format!("a_{}", c)));
+            exprs.push(ident(format!("b_{}", c)));
+        }
+        // the sections and the view of `--into` read the rows again, run the full join only once
+        let diff = joined
+            .select(exprs)?
+            .filter(col(STATUS).not_eq(lit("same")))?
+            .cache()
+            .await?;
+
+        let summary = diff
+            .clone()
+            .aggregate(vec![col(STATUS)], vec![count(lit(1)).alias("rows")])?
+            .sort(vec![col(STATUS).sort(true, false)])?;
+        let mut sections = vec![("Summary".to_string(), summary)];
+        if !values.is_empty() {
+            let columns = diff
+                .clone()
+                .filter(col(STATUS).eq(lit("changed")))?
+                .aggregate(
+                    vec![],
+                    values
+                        .iter()
+                        .map(|(c, as_string)| {
+                            Ok(
+                                sum(when(distinct(c, *as_string), lit(1)).otherwise(lit(0))?)
+                                    .alias(c),
+                            )
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?,
+                )?;
+            sections.push(("Changed rows per column".to_string(), columns));
+        }
+        for status in ["only_in_a", "only_in_b", "changed"] {
+            let samples = diff
+                .clone()
+                .filter(col(STATUS).eq(lit(status)))?
+                .limit(0, Some(sample))?;
+            sections.push((format!("Samples ({})", status), samples));
+        }
+
+        Ok(Self { diff, sections })
+    }
+}
+
+impl ReplDisplay for DataDiff {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let mut sections = vec![];
+        for (title, df) in self.sections {
+            let batches = df.collect().await?;
+            sections.push(format!(
+                "{}:\n{}",
+                title,
+                format_batches(&batches, &output)?
+            ));
+        }
+        Ok(sections.join("\n\n"))
+    }
+}
+
+fn prefixed(df: DataFrame, prefix: &str, marker: &str) -> anyhow::Result<DataFrame> {
+    let mut exprs: Vec<_> = df
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| ident(f.name()).alias(format!("{}_{}", prefix, f.name())))
+        .collect();
+    exprs.push(lit(true).alias(marker));
+    Ok(df.select(exprs)?)
+}
+
+fn distinct(column: &str, as_string: bool) -> Expr {
+    let (a, b) = (
+        ident(format!("a_{}", column)),
+        ident(format!("b_{}", column)),
+    );
+    let (a, b) = if as_string {
+        (cast(a, DataType::Utf8), cast(b, DataType::Utf8))
+    } else {
+        (a, b)
+    };
+    binary_expr(a, Operator::IsDistinctFrom, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::util::pretty::pretty_format_batches;
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_diff() -> anyhow::Result<()> {
+        let ctx = SessionContext::new();
+        let a = ctx
+            .sql("select * from (values (1, 'x', 10), (2, 'y', 20), (3, 'z', 30)) as t(id, name, amount)")
+            .await?;
+        let b = ctx
+            .sql("select * from (values (2, 'y', 21), (3, 'z', 30), (4, 'w', 40), (null, 'v', 50)) as t(id, name, amount)")
+            .await?;
+        // a null key never matches, the row is still only in b
+        let diff = DataDiff::try_new(a, b, &["id".to_string()], 10).await?;
+
+        let rows = diff
+            .diff
+            .clone()
+            .sort(vec![col("id").sort(true, false)])?
+            .collect()
+            .await?;
+        assert_eq!(
+            pretty_format_batches(&rows)?.to_string(),
+            "+----+-----------+------------------+--------+--------+----------+----------+\n\
+             | id | _status   | _changed_columns | a_name | b_name | a_amount | b_amount |\n\
+             +----+-----------+------------------+--------+--------+----------+----------+\n\
+             | 1  | only_in_a |                  | x      |        | 10       |          |\n\
+             | 2  | changed   | amount           | y      | y      | 20       | 21       |\n\
+             | 4  | only_in_b |                  |        | w      |          | 40       |\n\
+             |    | only_in_b |                  |        | v      |          | 50       |\n\
+             +----+-----------+------------------+--------+--------+----------+----------+"
+        );
+
+        let output = diff.display(OutputOptions::default()).await?;
+        assert!(
+            output.contains("| changed   | 1    |\n| only_in_a | 1    |\n| only_in_b | 2    |"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("| name | amount |\n+------+--------+\n| 0    | 1      |"),
+            "{}",
+            output
+        );
+        Ok(())
+    }
+}
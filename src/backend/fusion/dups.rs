@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::RecordBatch,
+    datatypes::{Field, Schema},
+};
+use datafusion::prelude::{DataFrame, SessionContext};
+
+use crate::{
+    backend::{format_batches, OutputOptions},
+    cli::KeepRow,
+    utils::quote_ident,
+    DupsOpts, ReplDisplay,
+};
+
+const DUPS_TABLE: &str = "__taotie_dups";
+// a reserved name for the rows of each key, shown as `count`
+const COUNT: &str = "__taotie_count";
+// the number of most repeated keys to show the rows of
+const SAMPLE_GROUPS: usize = 3;
+
+#[derive(Debug)]
+pub struct Duplicates {
+    // one row per key, only planned with `--into`
+    pub dedup: Option<DataFrame>,
+    sections: Vec<(String, DataFrame)>,
+}
+
+impl Duplicates {
+    pub async fn try_new(
+        ctx: &SessionContext,
+        df: DataFrame,
+        opts: &DupsOpts,
+    ) -> anyhow::Result<Self> {
+        let fields = df.schema().fields();
+        let columns: Vec<String> = fields.iter().map(|f| f.name().clone()).collect();
+        let keys = if opts.key.is_empty() {
+            fields
+                .iter()
+                .filter(|f| !f.data_type().is_nested())
+                .map(|f| f.name().clone())
+                .collect()
+        } else {
+            if let Some(k) = opts.key.iter().find(|k| !columns.contains(k)) {
+                anyhow::bail!("column not found: {}", k);
+            }
+            opts.key.clone()
+        };
+
+        ctx.register_table(DUPS_TABLE, df.into_view())?;
+        let ret = Self::plan(ctx, &columns, &keys, opts).await;
+        ctx.deregister_table(DUPS_TABLE)?;
+        ret
+    }
+
+    async fn plan(
+        ctx: &SessionContext,
+        columns: &[String],
+        keys: &[String],
+        opts: &DupsOpts,
+    ) -> anyhow::Result<Self> {
+        let key_list = quoted(keys).join(", ");
+        let groups = format!(
+            "SELECT {0}, count(*) AS {1} FROM {2} GROUP BY {0}",
+            key_list, COUNT, DUPS_TABLE
+        );
+        let top = format!(
+            "SELECT * FROM ({0}) AS g WHERE {1} > 1 ORDER BY {1} DESC, {2}",
+            groups, COUNT, key_list
+        );
+
+        let mut sections = vec![];
+        let summary = ctx
+            .sql(&format!(
+                "SELECT sum({0}) AS total_rows, count(*) AS distinct_keys, sum({0}) - count(*) AS duplicate_rows, sum(CASE WHEN {0} > 1 THEN 1 ELSE 0 END) AS duplicated_keys FROM ({1}) AS g",
+                COUNT, groups
+            ))
+            .await?;
+        sections.push(("Summary".to_string(), summary));
+        let most_repeated = ctx.sql(&format!("{} LIMIT {}", top, opts.top)).await?;
+        sections.push(("Most repeated keys".to_string(), most_repeated));
+
+        // rows of an exact duplicate are identical, only show groups for a key
+        if !opts.key.is_empty() {
+            let on = quoted(keys)
+                .iter()
+                .map(|k| format!("t.{0} IS NOT DISTINCT FROM k.{0}", k))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            let order = quoted(keys)
+                .iter()
+                .map(|k| format!("t.{}", k))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let samples = ctx
+                .sql(&format!(
+                    "SELECT t.* FROM {} AS t JOIN ({} LIMIT {}) AS k ON {} ORDER BY {}",
+                    DUPS_TABLE, top, SAMPLE_GROUPS, on, order
+                ))
+                .await?;
+            sections.push(("Sample groups".to_string(), samples));
+        }
+
+        let dedup = match &opts.into {
+            Some(_) => {
+                let sql = dedup_sql(columns, keys, opts.keep, opts.order_by.as_deref());
+                Some(ctx.sql(&sql).await?)
+            }
+            None => None,
+        };
+
+        Ok(Self { dedup, sections })
+    }
+}
+
+impl ReplDisplay for Duplicates {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let mut sections = vec![];
+        for (title, df) in self.sections {
+            let batches = df
+                .collect()
+                .await?
+                .into_iter()
+                .map(rename_count)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            sections.push(format!(
+                "{}:\n{}",
+                title,
+                format_batches(&batches, &output)?
+            ));
+        }
+        Ok(sections.join("\n\n"))
+    }
+}
+
+// only in the output, where a key also named `count` is no longer ambiguous
+fn rename_count(batch: RecordBatch) -> anyhow::Result<RecordBatch> {
+    let fields: Vec<Field> = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| match f.name() == COUNT {
+            true => f.as_ref().clone().with_name("count"),
+            false => f.as_ref().clone(),
+        })
+        .collect();
+    let schema = Arc::new(Schema::new(fields));
+    Ok(RecordBatch::try_new(schema, batch.columns().to_vec())?)
+}
+
+fn quoted(names: &[String]) -> Vec<String> {
+    names.iter().map(|n| quote_ident(n)).collect()
+}
+
+// keep one row per key, the first or last by `order_by`, or an arbitrary one without it
+fn dedup_sql(columns: &[String], keys: &[String], keep: KeepRow, order_by: Option<&str>) -> String {
+    let order = match order_by {
+        Some(column) => {
+            let direction = match keep {
+                KeepRow::First => "ASC",
+                KeepRow::Last => "DESC",
+            };
+            format!(" ORDER BY {} {}", quote_ident(column), direction)
+        }
+        None => String::new(),
+    };
+    format!(
+        "SELECT {} FROM (SELECT *, row_number() OVER (PARTITION BY {}{}) AS __taotie_rank FROM {}) AS r WHERE __taotie_rank = 1",
+        quoted(columns).join(", "),
+        quoted(keys).join(", "),
+        order,
+        DUPS_TABLE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_sql() {
+        let columns = vec!["id".to_string(), "ts".to_string()];
+        let keys = vec!["id".to_string()];
+        assert_eq!(
+            dedup_sql(&columns, &keys, KeepRow::Last, Some("ts")),
+            "SELECT \"id\", \"ts\" FROM (SELECT *, row_number() OVER (PARTITION BY \"id\" ORDER BY \"ts\" DESC) AS __taotie_rank FROM __taotie_dups) AS r WHERE __taotie_rank = 1"
+        );
+        assert!(dedup_sql(&columns, &keys, KeepRow::First, None)
+            .contains("OVER (PARTITION BY \"id\") AS __taotie_rank"));
+    }
+
+    #[tokio::test]
+    async fn test_count_key() -> anyhow::Result<()> {
+        let ctx = SessionContext::new();
+        let df = ctx
+            .sql("select * from (values (1, 'a'), (1, 'b'), (2, 'c')) as t(count, v)")
+            .await?;
+        let opts = DupsOpts::new(
+            "t".to_string(),
+            vec!["count".to_string()],
+            10,
+            None,
+            KeepRow::First,
+            None,
+        );
+        let output = Duplicates::try_new(&ctx, df, &opts)
+            .await?
+            .display(OutputOptions::default())
+            .await?;
+        assert!(
+            output.contains("| 3          | 2             | 1              | 1               |"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("| count | count |\n+-------+-------+\n| 1     | 2     |"),
+            "{}",
+            output
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use arrow::array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+use datafusion::{
+    datasource::physical_plan::{CsvExec, FileScanConfig, NdJsonExec, ParquetExec},
+    physical_plan::{collect, display::DisplayableExecutionPlan, ExecutionPlan},
+    prelude::{DataFrame, SessionContext},
+};
+
+use crate::{
+    backend::{format_batches, OutputOptions},
+    utils::human_bytes,
+    ReplDisplay,
+};
+
+#[derive(Debug)]
+pub struct QueryPlan {
+    logical: String,
+    physical: String,
+    scans: Vec<ScanInfo>,
+    // the rows returned and wall time, only with `--analyze`
+    analyzed: Option<(usize, Duration)>,
+}
+
+// how much of a file scan was pushed down to the reader
+#[derive(Debug)]
+struct ScanInfo {
+    operator: &'static str,
+    files: usize,
+    columns: String,
+    predicate: String,
+    output_rows: Option<u64>,
+    row_groups_pruned: Option<u64>,
+    bytes_scanned: Option<u64>,
+}
+
+impl QueryPlan {
+    pub async fn try_new(
+        ctx: &SessionContext,
+        df: DataFrame,
+        analyze: bool,
+    ) -> anyhow::Result<Self> {
+        let logical = df.clone().into_optimized_plan()?;
+        let plan = df.create_physical_plan().await?;
+
+        let analyzed = if analyze {
+            let start = Instant::now();
+            let batches = collect(plan.clone(), ctx.task_ctx()).await?;
+            let rows = batches.iter().map(|b| b.num_rows()).sum();
+            Some((rows, start.elapsed()))
+        } else {
+            None
+        };
+        let physical = match analyze {
+            true => DisplayableExecutionPlan::with_metrics(plan.as_ref()),
+            false => DisplayableExecutionPlan::new(plan.as_ref()),
+        };
+        let physical = physical.indent(true).to_string();
+        let logical = logical.display_indent().to_string();
+
+        let mut scans = vec![];
+        collect_scans(plan.as_ref(), analyze, &mut scans);
+
+        Ok(Self {
+            logical,
+            physical,
+            scans,
+            analyzed,
+        })
+    }
+}
+
+// metrics are only populated once the plan has run
+fn collect_scans(plan: &dyn ExecutionPlan, analyzed: bool, scans: &mut Vec<ScanInfo>) {
+    let any = plan.as_any();
+    let scan = if let Some(exec) = any.downcast_ref::<ParquetExec>() {
+        let predicate = exec
+            .predicate()
+            .map_or("none".to_string(), |p| p.to_string());
+        Some(ScanInfo::new("ParquetExec", exec.base_config(), predicate))
+    } else if let Some(exec) = any.downcast_ref::<CsvExec>() {
+        Some(ScanInfo::new("CsvExec", exec.base_config(), "n/a".into()))
+    } else {
+        any.downcast_ref::<NdJsonExec>()
+            .map(|exec| ScanInfo::new("NdJsonExec", exec.base_config(), "n/a".into()))
+    };
+
+    if let Some(mut scan) = scan {
+        if let Some(metrics) = plan.metrics().filter(|_| analyzed) {
+            let sum = |name: &str| metrics.sum_by_name(name).map(|v| v.as_usize() as u64);
+            scan.output_rows = metrics.output_rows().map(|v| v as u64);
+            scan.row_groups_pruned = match (
+                sum("row_groups_pruned_statistics"),
+                sum("row_groups_pruned_bloom_filter"),
+            ) {
+                (None, None) => None,
+                (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+            };
+            scan.bytes_scanned = sum("bytes_scanned");
+        }
+        scans.push(scan);
+    }
+
+    for child in plan.children() {
+        collect_scans(child.as_ref(), analyzed, scans);
+    }
+}
+
+impl ScanInfo {
+    fn new(operator: &'static str, config: &FileScanConfig, predicate: String) -> Self {
+        let total = config.file_schema.fields().len();
+        let columns = match &config.projection {
+            Some(projection) => format!("{} of {}", projection.len(), total),
+            None => format!("all {}", total),
+        };
+        Self {
+            operator,
+            files: config.file_groups.iter().map(|g| g.len()).sum(),
+            columns,
+            predicate,
+            output_rows: None,
+            row_groups_pruned: None,
+            bytes_scanned: None,
+        }
+    }
+}
+
+impl ReplDisplay for QueryPlan {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let mut sections = vec![
+            format!("Logical plan:\n{}", self.logical),
+            format!("Physical plan:\n{}", self.physical),
+        ];
+
+        if !self.scans.is_empty() {
+            let scans = &self.scans;
+            let strings = |f: fn(&ScanInfo) -> String| {
+                Arc::new(StringArray::from_iter_values(scans.iter().map(f))) as ArrayRef
+            };
+            let numbers = |f: fn(&ScanInfo) -> Option<u64>| {
+                Arc::new(UInt64Array::from_iter(scans.iter().map(f))) as ArrayRef
+            };
+            let batch = RecordBatch::try_from_iter(vec![
+                ("operator", strings(|s| s.operator.to_string())),
+                ("files", numbers(|s| Some(s.files as u64))),
+                ("columns", strings(|s| s.columns.clone())),
+                ("predicate", strings(|s| s.predicate.clone())),
+                ("output_rows", numbers(|s| s.output_rows)),
+                ("row_groups_pruned", numbers(|s| s.row_groups_pruned)),
+                (
+                    "bytes_scanned",
+                    Arc::new(StringArray::from_iter(
+                        scans.iter().map(|s| s.bytes_scanned.map(human_bytes)),
+                    )) as ArrayRef,
+                ),
+            ])?;
+            sections.push(format!("Scans:\n{}", format_batches(&[batch], &output)?));
+        }
+
+        if let Some((rows, elapsed)) = self.analyzed {
+            sections.push(format!("Returned {} rows in {:.3?}", rows, elapsed));
+        }
+        Ok(sections.join("\n\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Int64Array;
+    use datafusion::prelude::ParquetReadOptions;
+
+    use super::*;
+    use crate::test_utils::TestDir;
+
+    #[tokio::test]
+    async fn test_explain() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+        )])?;
+        let path = dir.write_parquet("t.parquet", &batch, 2)?;
+        let ctx = SessionContext::new();
+        ctx.register_parquet("t", &path, ParquetReadOptions::default())
+            .await?;
+
+        let sql = "select id from t where id > 2";
+        let plan = QueryPlan::try_new(&ctx, ctx.sql(sql).await?, false).await?;
+        assert_eq!(plan.scans.len(), 1);
+        assert_eq!(plan.scans[0].output_rows, None);
+        let output = plan.display(OutputOptions::default()).await?;
+        assert!(output.starts_with("Logical plan:\n"), "{}", output);
+        assert!(output.contains("\n\nPhysical plan:\n"), "{}", output);
+        assert!(output.contains("ParquetExec"), "{}", output);
+        assert!(!output.contains("Returned"), "{}", output);
+
+        // the group of ids 1 and 2 is pruned by its statistics
+        let plan = QueryPlan::try_new(&ctx, ctx.sql(sql).await?, true).await?;
+        let scan = &plan.scans[0];
+        assert_eq!(scan.row_groups_pruned, Some(1));
+        assert_eq!(scan.output_rows, Some(2));
+        assert!(scan.bytes_scanned.is_some());
+        let output = plan.display(OutputOptions::default()).await?;
+        assert!(output.contains("metrics=[output_rows="), "{}", output);
+        assert!(output.contains("Returned 2 rows in "), "{}", output);
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use std::{fmt::Display, pin::Pin, sync::Arc};
+
+use arrow::{array::RecordBatch, datatypes::Schema};
+use arrow_flight::{
+    encode::FlightDataEncoderBuilder,
+    error::FlightError,
+    flight_service_server::{FlightService, FlightServiceServer},
+    sql::{
+        metadata::{SqlInfoData, SqlInfoDataBuilder},
+        server::FlightSqlService,
+        CommandGetCatalogs, CommandGetDbSchemas, CommandGetSqlInfo, CommandGetTables,
+        CommandStatementQuery, ProstMessageExt, SqlInfo, TicketStatementQuery,
+    },
+    FlightData, FlightDescriptor, FlightEndpoint, FlightInfo, Ticket,
+};
+use futures::{stream, Stream, TryStreamExt};
+use prost::Message;
+use tokio::net::TcpListener;
+use tonic::{
+    transport::{server::TcpIncoming, Server},
+    Request, Response, Status,
+};
+
+use super::DataFusionBackend;
+
+type DoGetStream = Pin<Box<dyn Stream<Item = Result<FlightData, Status>> + Send + 'static>>;
+
+/// Serve the datasets of the backend over Arrow Flight SQL until the process stops. Results
+/// stream as arrow batches, and queries are read only.
+pub async fn serve(backend: Arc<DataFusionBackend>, listener: TcpListener) -> anyhow::Result<()> {
+    let incoming =
+        TcpIncoming::from_listener(listener, true, None).map_err(|e| anyhow::anyhow!("{}", e))?;
+    let service = FlightServiceServer::new(FlightSqlServer::try_new(backend)?);
+    Server::builder()
+        .add_service(service)
+        .serve_with_incoming(incoming)
+        .await?;
+    Ok(())
+}
+
+struct FlightSqlServer {
+    backend: Arc<DataFusionBackend>,
+    sql_info: SqlInfoData,
+}
+
+impl FlightSqlServer {
+    fn try_new(backend: Arc<DataFusionBackend>) -> anyhow::Result<Self> {
+        let mut builder = SqlInfoDataBuilder::new();
+        builder.append(SqlInfo::FlightSqlServerName, "taotie");
+        builder.append(SqlInfo::FlightSqlServerVersion, env!("CARGO_PKG_VERSION"));
+        builder.append(SqlInfo::FlightSqlServerReadOnly, true);
+        Ok(Self {
+            backend,
+            sql_info: builder.build()?,
+        })
+    }
+
+    // the datasets, which are registered in the default catalog and schema, read from the
+    // schema provider rather than the files so that listing them stays cheap
+    async fn tables(&self) -> Result<Vec<(String, String, String, Schema)>, Status> {
+        let (catalog, schema) = self.backend.default_schema();
+        let Some(provider) = self
+            .backend
+            .catalog(&catalog)
+            .and_then(|c| c.schema(&schema))
+        else {
+            return Ok(vec![]);
+        };
+        let mut names = provider.table_names();
+        names.sort();
+        let mut tables = vec![];
+        for name in names {
+            if let Some(table) = provider.table(&name).await.map_err(status)? {
+                let table_schema = table.schema().as_ref().clone();
+                tables.push((catalog.clone(), schema.clone(), name, table_schema));
+            }
+        }
+        Ok(tables)
+    }
+}
+
+#[tonic::async_trait]
+impl FlightSqlService for FlightSqlServer {
+    type FlightService = Self;
+
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let df = self
+            .backend
+            .read_only_sql(&query.query)
+            .await
+            .map_err(status)?;
+        let ticket = TicketStatementQuery {
+            statement_handle: query.query.into(),
+        };
+        flight_info(ticket, df.schema().as_arrow(), request.into_inner())
+    }
+
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let sql = String::from_utf8(ticket.statement_handle.to_vec()).map_err(status)?;
+        let df = self.backend.read_only_sql(&sql).await.map_err(status)?;
+        let batches = df
+            .execute_stream()
+            .await
+            .map_err(status)?
+            .map_err(|e| FlightError::ExternalError(Box::new(e)));
+        let stream = FlightDataEncoderBuilder::new()
+            .build(batches)
+            .map_err(Status::from);
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn get_flight_info_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder().schema();
+        flight_info(query, &schema, request.into_inner())
+    }
+
+    async fn do_get_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let mut builder = query.into_builder();
+        builder.append(self.backend.default_schema().0);
+        batch_stream(builder.build())
+    }
+
+    async fn get_flight_info_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder().schema();
+        flight_info(query, &schema, request.into_inner())
+    }
+
+    async fn do_get_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let mut builder = query.into_builder();
+        let (catalog, schema) = self.backend.default_schema();
+        builder.append(catalog, schema);
+        batch_stream(builder.build())
+    }
+
+    async fn get_flight_info_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder().schema();
+        flight_info(query, &schema, request.into_inner())
+    }
+
+    async fn do_get_tables(
+        &self,
+        query: CommandGetTables,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let mut builder = query.into_builder();
+        for (catalog, schema, table, table_schema) in self.tables().await? {
+            builder
+                .append(catalog, schema, table, "TABLE", &table_schema)
+                .map_err(status)?;
+        }
+        batch_stream(builder.build())
+    }
+
+    async fn get_flight_info_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        flight_info(query, &self.sql_info.schema(), request.into_inner())
+    }
+
+    async fn do_get_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        batch_stream(self.sql_info.record_batch(query.info))
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+}
+
+fn status(e: impl Display) -> Status {
+    Status::internal(e.to_string())
+}
+
+// a single endpoint whose ticket is the command itself, fetched back with `do_get`
+#[allow(clippy::result_large_err)]
+fn flight_info(
+    command: impl ProstMessageExt,
+    schema: &Schema,
+    descriptor: FlightDescriptor,
+) -> Result<Response<FlightInfo>, Status> {
+    let ticket = Ticket::new(command.as_any().encode_to_vec());
+    let info = FlightInfo::new()
+        .try_with_schema(schema)
+        .map_err(status)?
+        .with_endpoint(FlightEndpoint::new().with_ticket(ticket))
+        .with_descriptor(descriptor);
+    Ok(Response::new(info))
+}
+
+#[allow(clippy::result_large_err)]
+fn batch_stream(batch: Result<RecordBatch, impl Display>) -> Result<Response<DoGetStream>, Status> {
+    let batch = batch.map_err(status)?;
+    let stream = FlightDataEncoderBuilder::new()
+        .build(stream::iter([Ok(batch)]))
+        .map_err(Status::from);
+    Ok(Response::new(Box::pin(stream)))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{ArrayRef, Int64Array, StringArray},
+        compute::concat_batches,
+    };
+    use arrow_flight::sql::client::FlightSqlServiceClient;
+    use datafusion::datasource::MemTable;
+    use tonic::transport::Endpoint;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_execute() -> anyhow::Result<()> {
+        let backend = DataFusionBackend::new();
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+            ),
+        ])?;
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+        backend.register_table("t", Arc::new(table))?;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(serve(Arc::new(backend), listener));
+
+        let channel = Endpoint::from_shared(format!("http://{}", addr))?
+            .connect()
+            .await?;
+        let mut client = FlightSqlServiceClient::new(channel);
+        let info = client
+            .execute(
+                "select id, name from t where id > 1 order by id".to_string(),
+                None,
+            )
+            .await?;
+        let mut batches = vec![];
+        for endpoint in info.endpoint {
+            let ticket = endpoint.ticket.expect("expect ticket");
+            let stream = client.do_get(ticket).await?;
+            batches.extend(stream.try_collect::<Vec<_>>().await?);
+        }
+        let batch = concat_batches(&batches[0].schema(), &batches)?;
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &Int64Array::from(vec![2, 3]) as &dyn arrow::array::Array
+        );
+
+        // read only
+        assert!(client
+            .execute("drop table t".to_string(), None)
+            .await
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray, RecordBatch, StringArray, UInt64Array},
+    datatypes::{DataType, Float64Type, Int64Type, TimeUnit},
+};
+use datafusion::{
+    functions_aggregate::count::count,
+    prelude::{cast, col, date_trunc, ident, lit, max, min, when, DataFrame},
+};
+
+use crate::{
+    backend::{format_batches, OutputFormat, OutputOptions},
+    utils::{bar, sparkline},
+    ReplDisplay,
+};
+
+const BAR_WIDTH: usize = 50;
+const SPARKLINE_BINS: usize = 16;
+const NANOS_PER_DAY: i64 = 24 * 3600 * 1_000_000_000;
+
+#[derive(Debug)]
+pub(super) struct Histogram {
+    column: String,
+    bins: Vec<(String, u64)>,
+}
+
+impl Histogram {
+    pub async fn try_new(df: DataFrame, column: &str, bins: usize) -> anyhow::Result<Self> {
+        let field = df.schema().field_with_unqualified_name(column)?;
+        let dt = field.data_type().clone();
+        let df = df.filter(ident(column).is_not_null())?;
+        let bins = match dt {
+            DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => {
+                temporal_bins(df, column).await?
+            }
+            dt if dt.is_numeric() => numeric_bins(df, column, bins.max(1)).await?,
+            dt => anyhow::bail!("cannot build a histogram for {} column {}", dt, column),
+        };
+        Ok(Self {
+            column: column.to_string(),
+            bins,
+        })
+    }
+
+    pub fn sparkline(&self) -> String {
+        let counts: Vec<_> = self.bins.iter().map(|(_, c)| *c).collect();
+        sparkline(&counts)
+    }
+}
+
+// one sparkline per numeric column, used by `describe --sparkline`
+pub(super) async fn sparklines(df: &DataFrame) -> anyhow::Result<RecordBatch> {
+    let mut columns = vec![];
+    let mut lines = vec![];
+    for field in df.schema().fields().iter() {
+        if !field.data_type().is_numeric() {
+            continue;
+        }
+        let hist = Histogram::try_new(df.clone(), field.name(), SPARKLINE_BINS).await?;
+        columns.push(field.name().clone());
+        lines.push(hist.sparkline());
+    }
+    let batch = RecordBatch::try_from_iter(vec![
+        ("column", Arc::new(StringArray::from(columns)) as ArrayRef),
+        ("sparkline", Arc::new(StringArray::from(lines)) as ArrayRef),
+    ])?;
+    Ok(batch)
+}
+
+impl ReplDisplay for Histogram {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        // the bars are only drawn in a table, csv and json get one row per bin
+        if output.format != OutputFormat::Table {
+            let (bins, counts): (Vec<_>, Vec<_>) = self.bins.into_iter().unzip();
+            let batch = RecordBatch::try_from_iter(vec![
+                ("bin", Arc::new(StringArray::from(bins)) as ArrayRef),
+                ("count", Arc::new(UInt64Array::from(counts)) as ArrayRef),
+            ])?;
+            return format_batches(&[batch], &output);
+        }
+        let total: u64 = self.bins.iter().map(|(_, c)| c).sum();
+        let max = self.bins.iter().map(|(_, c)| *c).max().unwrap_or(0);
+        let label_width = self.bins.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+
+        let mut lines = vec![format!("{} ({} non-null values)", self.column, total)];
+        for (label, count) in self.bins.iter() {
+            lines.push(format!(
+                "{:>label_width$} | {:<bar_width$} {}",
+                label,
+                bar(*count, max, BAR_WIDTH),
+                count,
+                bar_width = BAR_WIDTH,
+            ));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+async fn numeric_bins(
+    df: DataFrame,
+    column: &str,
+    bins: usize,
+) -> anyhow::Result<Vec<(String, u64)>> {
+    let value = cast(ident(column), DataType::Float64);
+    let stats = df
+        .clone()
+        .aggregate(vec![], vec![min(value.clone()), max(value.clone())])?
+        .collect()
+        .await?;
+    let (lo, hi) = match stats.first() {
+        Some(batch) if batch.num_rows() > 0 && !batch.column(0).is_null(0) => (
+            batch.column(0).as_primitive::<Float64Type>().value(0),
+            batch.column(1).as_primitive::<Float64Type>().value(0),
+        ),
+        _ => return Ok(vec![]),
+    };
+    let (bins, width) = if hi > lo {
+        (bins, (hi - lo) / bins as f64)
+    } else {
+        (1, 1.0)
+    };
+
+    let last = lit(bins as i64 - 1);
+    let bin = cast((value - lit(lo)) / lit(width), DataType::Int64);
+    let bin = when(bin.clone().gt(last.clone()), last).otherwise(bin)?;
+    let batches = df
+        .aggregate(vec![bin.alias("bin")], vec![count(lit(1)).alias("count")])?
+        .collect()
+        .await?;
+
+    let mut counts = vec![0; bins];
+    let last = counts.len() as i64 - 1;
+    for batch in batches.iter() {
+        let bins = batch.column(0).as_primitive::<Int64Type>();
+        let values = batch.column(1).as_primitive::<Int64Type>();
+        for (bin, count) in bins.iter().zip(values.iter()) {
+            if let (Some(bin), Some(count)) = (bin, count) {
+                counts[bin.clamp(0, last) as usize] += count as u64;
+            }
+        }
+    }
+
+    let label = |v: f64| {
+        format!("{:.4}", v)
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    };
+    Ok(counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let start = lo + width * i as f64;
+            let end = if i + 1 == bins { hi } else { start + width };
+            let close = if i + 1 == bins { ']' } else { ')' };
+            (format!("[{}, {}{}", label(start), label(end), close), count)
+        })
+        .collect())
+}
+
+// bin temporal values by day, week or month depending on the time span
+async fn temporal_bins(df: DataFrame, column: &str) -> anyhow::Result<Vec<(String, u64)>> {
+    let ts = cast(
+        ident(column),
+        DataType::Timestamp(TimeUnit::Nanosecond, None),
+    );
+    let stats = df
+        .clone()
+        .aggregate(
+            vec![],
+            vec![min(ts.clone()).alias("lo"), max(ts.clone()).alias("hi")],
+        )?
+        .select(vec![
+            cast(col("lo"), DataType::Int64),
+            cast(col("hi"), DataType::Int64),
+        ])?
+        .collect()
+        .await?;
+    let days = match stats.first() {
+        Some(batch) if batch.num_rows() > 0 && !batch.column(0).is_null(0) => {
+            let lo = batch.column(0).as_primitive::<Int64Type>().value(0);
+            let hi = batch.column(1).as_primitive::<Int64Type>().value(0);
+            (hi - lo) / NANOS_PER_DAY
+        }
+        _ => return Ok(vec![]),
+    };
+    let (unit, label_len) = match days {
+        0..=62 => ("day", 10),
+        63..=366 => ("week", 10),
+        367..=3660 => ("month", 7),
+        _ => ("year", 4),
+    };
+
+    let batches = df
+        .aggregate(
+            vec![date_trunc(lit(unit), ts).alias("bucket")],
+            vec![count(lit(1)).alias("count")],
+        )?
+        .sort(vec![col("bucket").sort(true, false)])?
+        .select(vec![
+            cast(col("bucket"), DataType::Utf8).alias("bucket"),
+            col("count"),
+        ])?
+        .collect()
+        .await?;
+
+    let mut bins = vec![];
+    for batch in batches.iter() {
+        let buckets = batch.column(0).as_string::<i32>();
+        let counts = batch.column(1).as_primitive::<Int64Type>();
+        for (bucket, count) in buckets.iter().zip(counts.iter()) {
+            if let (Some(bucket), Some(count)) = (bucket, count) {
+                let label = bucket.chars().take(label_len).collect();
+                bins.push((label, count as u64));
+            }
+        }
+    }
+    Ok(bins)
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    async fn histogram(sql: &str, bins: usize) -> anyhow::Result<Vec<(String, u64)>> {
+        let df = SessionContext::new().sql(sql).await?;
+        Ok(Histogram::try_new(df, "v", bins).await?.bins)
+    }
+
+    fn bins(expected: &[(&str, u64)]) -> Vec<(String, u64)> {
+        expected.iter().map(|(l, c)| (l.to_string(), *c)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_numeric_bins() -> anyhow::Result<()> {
+        let sql = "select * from unnest([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, null]) as t(v)";
+        let expected = [
+            ("[0, 2)", 2),
+            ("[2, 4)", 2),
+            ("[4, 6)", 2),
+            ("[6, 8)", 2),
+            ("[8, 10]", 3),
+        ];
+        assert_eq!(histogram(sql, 5).await?, bins(&expected));
+        Ok(())
+    }
+
+    // the max value falls past the last bin and is clamped into it, as is a single value
+    #[tokio::test]
+    async fn test_last_bin() -> anyhow::Result<()> {
+        let sql = "select * from unnest([0.5, 1.5, 2.5]) as t(v)";
+        let expected = [("[0.5, 1.5)", 1), ("[1.5, 2.5]", 2)];
+        assert_eq!(histogram(sql, 2).await?, bins(&expected));
+
+        let sql = "select * from unnest([5, 5, 5]) as t(v)";
+        assert_eq!(histogram(sql, 10).await?, bins(&[("[5, 5]", 3)]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_temporal_bins() -> anyhow::Result<()> {
+        let sql = "select cast(v as date) as v \
+                   from unnest(['2024-01-01', '2024-01-01', '2024-01-03']) as t(v)";
+        let expected = [("2024-01-01", 2), ("2024-01-03", 1)];
+        assert_eq!(histogram(sql, 10).await?, bins(&expected));
+
+        let sql = "select cast(v as timestamp) as v \
+                   from unnest(['2023-01-15', '2023-03-20', '2024-06-01']) as t(v)";
+        let expected = [("2023-01", 1), ("2023-03", 1), ("2024-06", 1)];
+        assert_eq!(histogram(sql, 10).await?, bins(&expected));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_all_null() -> anyhow::Result<()> {
+        let sql = "select cast(v as int) as v from unnest([null, null]) as t(v)";
+        let df = SessionContext::new().sql(sql).await?;
+        let hist = Histogram::try_new(df, "v", 10).await?;
+        assert!(hist.bins.is_empty());
+        let output = hist.display(OutputOptions::default()).await?;
+        assert_eq!(output, "v (0 non-null values)");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_csv_output() -> anyhow::Result<()> {
+        let sql = "select * from unnest([1, 2, 2]) as t(v)";
+        let df = SessionContext::new().sql(sql).await?;
+        let hist = Histogram::try_new(df, "v", 2).await?;
+        let output = OutputOptions {
+            format: OutputFormat::Csv,
+            max_rows: None,
+        };
+        assert_eq!(
+            hist.display(output).await?,
+            "bin,count\n\"[1, 1.5)\",1\n\"[1.5, 2]\",2\n"
+        );
+        Ok(())
+    }
+}
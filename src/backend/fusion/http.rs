@@ -0,0 +1,402 @@
+use arrow::{
+    array::RecordBatch, csv, datatypes::Schema, ipc::writer::StreamWriter,
+    json::LineDelimitedWriter,
+};
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use datafusion::prelude::{DataFrame, SessionContext};
+use futures::{stream, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+
+use super::{describe::DataFrameDescriber, read_only};
+
+/// Serve the tables of the session over HTTP until the process stops. Results are encoded
+/// by the `Accept` header, JSON by default, and stream with at most `max_rows` rows. Queries
+/// are read only.
+pub async fn serve(
+    ctx: SessionContext,
+    max_rows: Option<usize>,
+    listener: TcpListener,
+) -> anyhow::Result<()> {
+    axum::serve(listener, router(HttpState { ctx, max_rows })).await?;
+    Ok(())
+}
+
+fn router(state: HttpState) -> Router {
+    Router::new()
+        .route("/query", post(query))
+        .route("/datasets", get(datasets))
+        .route("/datasets/:name/schema", get(schema))
+        .route("/datasets/:name/describe", get(describe))
+        .with_state(state)
+}
+
+#[derive(Clone)]
+struct HttpState {
+    ctx: SessionContext,
+    // `None` for no cap on the rows of a response
+    max_rows: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryRequest {
+    sql: String,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Json,
+    NdJson,
+    Csv,
+    Arrow,
+}
+
+// encodes the batches of a result one at a time, so that the response streams
+struct BatchEncoder {
+    encoding: Encoding,
+    // whether a batch was encoded, for the CSV header and the JSON separators
+    started: bool,
+    // the IPC stream of `Encoding::Arrow`, which frames the batches with the schema
+    arrow: Option<StreamWriter<Vec<u8>>>,
+}
+
+struct HttpError(StatusCode, String);
+
+type HttpResult = Result<Response, HttpError>;
+
+async fn query(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    Json(request): Json<QueryRequest>,
+) -> HttpResult {
+    let df = state
+        .ctx
+        .sql_with_options(&request.sql, read_only())
+        .await
+        .map_err(HttpError::bad_request)?;
+    respond(df, Encoding::accepted(&headers), state.limit(request.limit)).await
+}
+
+async fn datasets(State(state): State<HttpState>, headers: HeaderMap) -> HttpResult {
+    let sql = "select table_name as name, table_type as type from information_schema.tables where table_schema = 'public' order by table_name";
+    let df = state.ctx.sql(sql).await.map_err(HttpError::internal)?;
+    respond(df, Encoding::accepted(&headers), state.limit(None)).await
+}
+
+async fn schema(State(state): State<HttpState>, Path(name): Path<String>) -> HttpResult {
+    let df = state.ctx.table(&name).await.map_err(HttpError::not_found)?;
+    let fields: Vec<_> = df
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| {
+            json!({
+                "name": field.name(),
+                "type": field.data_type().to_string(),
+                "nullable": field.is_nullable(),
+            })
+        })
+        .collect();
+    Ok(Json(fields).into_response())
+}
+
+async fn describe(
+    State(state): State<HttpState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> HttpResult {
+    let df = state.ctx.table(&name).await.map_err(HttpError::not_found)?;
+    let describer = DataFrameDescriber::try_new(df).map_err(HttpError::internal)?;
+    let stats = describer.describe().await.map_err(HttpError::internal)?;
+    respond(stats, Encoding::accepted(&headers), state.limit(None)).await
+}
+
+// an error after the first batch aborts the response, as the status is already sent
+async fn respond(df: DataFrame, encoding: Encoding, limit: Option<usize>) -> HttpResult {
+    let df = match limit {
+        Some(n) => df.limit(0, Some(n)).map_err(HttpError::internal)?,
+        None => df,
+    };
+    let encoder =
+        BatchEncoder::try_new(encoding, df.schema().as_arrow()).map_err(HttpError::internal)?;
+    let batches = df.execute_stream().await.map_err(HttpError::internal)?;
+    let chunks = stream::unfold(Some((batches, encoder)), |state| async move {
+        let (mut batches, mut encoder) = state?;
+        match batches.next().await {
+            Some(batch) => {
+                let chunk = batch
+                    .map_err(anyhow::Error::from)
+                    .and_then(|batch| encoder.encode(&batch));
+                Some((chunk, Some((batches, encoder))))
+            }
+            None => Some((encoder.finish(), None)),
+        }
+    });
+    Ok((
+        [(header::CONTENT_TYPE, encoding.content_type())],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+impl HttpState {
+    // the limit of the request, capped by `max_rows`
+    fn limit(&self, requested: Option<usize>) -> Option<usize> {
+        match (requested, self.max_rows) {
+            (Some(requested), Some(max_rows)) => Some(requested.min(max_rows)),
+            (requested, max_rows) => requested.or(max_rows),
+        }
+    }
+}
+
+impl Encoding {
+    // the first supported type of the `Accept` header, JSON for anything else
+    fn accepted(headers: &HeaderMap) -> Self {
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        accept
+            .split(',')
+            .filter_map(|t| match t.split(';').next().unwrap_or_default().trim() {
+                "application/json" => Some(Encoding::Json),
+                "application/x-ndjson" => Some(Encoding::NdJson),
+                "text/csv" => Some(Encoding::Csv),
+                "application/vnd.apache.arrow.stream" => Some(Encoding::Arrow),
+                _ => None,
+            })
+            .next()
+            .unwrap_or(Encoding::Json)
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::NdJson => "application/x-ndjson",
+            Encoding::Csv => "text/csv",
+            Encoding::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+}
+
+impl BatchEncoder {
+    fn try_new(encoding: Encoding, schema: &Schema) -> anyhow::Result<Self> {
+        let arrow = match encoding {
+            Encoding::Arrow => Some(StreamWriter::try_new(vec![], schema)?),
+            _ => None,
+        };
+        Ok(Self {
+            encoding,
+            started: false,
+            arrow,
+        })
+    }
+
+    fn encode(&mut self, batch: &RecordBatch) -> anyhow::Result<Vec<u8>> {
+        let chunk = match (self.encoding, &mut self.arrow) {
+            (Encoding::Json, _) => {
+                let rows = ndjson(batch)?;
+                if rows.is_empty() {
+                    return Ok(rows);
+                }
+                // newlines only separate the rows, as those in values are escaped
+                let separator = if self.started { b',' } else { b'[' };
+                let rows = rows.trim_ascii_end().iter();
+                let rows = rows.map(|&b| if b == b'\n' { b',' } else { b });
+                std::iter::once(separator).chain(rows).collect()
+            }
+            (Encoding::NdJson, _) => ndjson(batch)?,
+            (Encoding::Csv, _) => {
+                let mut buf = vec![];
+                let mut writer = csv::WriterBuilder::new()
+                    .with_header(!self.started)
+                    .build(&mut buf);
+                writer.write(batch)?;
+                drop(writer);
+                buf
+            }
+            (Encoding::Arrow, arrow) => {
+                let writer = arrow.as_mut().expect("expect arrow writer");
+                writer.write(batch)?;
+                std::mem::take(writer.get_mut())
+            }
+        };
+        self.started = true;
+        Ok(chunk)
+    }
+
+    fn finish(&mut self) -> anyhow::Result<Vec<u8>> {
+        match (self.encoding, &mut self.arrow) {
+            (Encoding::Json, _) if self.started => Ok(b"]".to_vec()),
+            (Encoding::Json, _) => Ok(b"[]".to_vec()),
+            (Encoding::Arrow, Some(writer)) => {
+                writer.finish()?;
+                Ok(std::mem::take(writer.get_mut()))
+            }
+            _ => Ok(vec![]),
+        }
+    }
+}
+
+fn ndjson(batch: &RecordBatch) -> anyhow::Result<Vec<u8>> {
+    let mut writer = LineDelimitedWriter::new(vec![]);
+    writer.write(batch)?;
+    writer.finish()?;
+    Ok(writer.into_inner())
+}
+
+impl HttpError {
+    fn bad_request(e: impl ToString) -> Self {
+        Self(StatusCode::BAD_REQUEST, e.to_string())
+    }
+
+    fn not_found(e: impl ToString) -> Self {
+        Self(StatusCode::NOT_FOUND, e.to_string())
+    }
+
+    fn internal(e: impl ToString) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+impl IntoResponse for HttpError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, Int64Array, StringArray},
+        ipc::reader::StreamReader,
+    };
+    use axum::{body::to_bytes, http::HeaderValue, http::Request};
+    use datafusion::datasource::MemTable;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    // a table of two batches, so that encodings cross a batch boundary
+    fn state(max_rows: Option<usize>) -> anyhow::Result<HttpState> {
+        let batch = |ids: Vec<i64>, names: Vec<&str>| {
+            RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("name", Arc::new(StringArray::from(names)) as ArrayRef),
+            ])
+        };
+        let batches = vec![
+            batch(vec![1, 2], vec!["a", "b"])?,
+            batch(vec![3], vec!["c"])?,
+        ];
+        let table = MemTable::try_new(batches[0].schema(), vec![batches])?;
+        let ctx = SessionContext::new();
+        ctx.register_table("t", Arc::new(table))?;
+        Ok(HttpState { ctx, max_rows })
+    }
+
+    async fn post_query(
+        state: HttpState,
+        body: serde_json::Value,
+        accept: &str,
+    ) -> anyhow::Result<(StatusCode, Vec<u8>)> {
+        let request = Request::post("/query")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ACCEPT, accept)
+            .body(Body::from(body.to_string()))?;
+        let response = router(state).oneshot(request).await?;
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        Ok((status, body.to_vec()))
+    }
+
+    #[tokio::test]
+    async fn test_query() -> anyhow::Result<()> {
+        let sql = json!({ "sql": "select * from t" });
+        let (status, body) = post_query(state(None)?, sql.clone(), "application/json").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body)?,
+            json!([{"id": 1, "name": "a"}, {"id": 2, "name": "b"}, {"id": 3, "name": "c"}])
+        );
+
+        let (_, body) = post_query(state(None)?, sql.clone(), "text/csv").await?;
+        assert_eq!(String::from_utf8(body)?, "id,name\n1,a\n2,b\n3,c\n");
+
+        let (_, body) =
+            post_query(state(None)?, sql, "application/vnd.apache.arrow.stream").await?;
+        let reader = StreamReader::try_new(body.as_slice(), None)?;
+        let rows: usize = reader
+            .map(|b| b.map(|b| b.num_rows()))
+            .sum::<Result<_, _>>()?;
+        assert_eq!(rows, 3);
+
+        let (status, _) = post_query(
+            state(None)?,
+            json!({ "sql": "drop table t" }),
+            "application/json",
+        )
+        .await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_query_limit() -> anyhow::Result<()> {
+        let sql = json!({ "sql": "select id from t order by id", "limit": 2 });
+        let (_, body) = post_query(state(None)?, sql.clone(), "application/x-ndjson").await?;
+        assert_eq!(String::from_utf8(body)?, "{\"id\":1}\n{\"id\":2}\n");
+
+        // the server caps the limit of the request
+        let (_, body) = post_query(state(Some(1))?, sql, "application/x-ndjson").await?;
+        assert_eq!(String::from_utf8(body)?, "{\"id\":1}\n");
+
+        let sql = json!({ "sql": "select id from t where id > 3" });
+        let (_, body) = post_query(state(None)?, sql, "application/json").await?;
+        assert_eq!(String::from_utf8(body)?, "[]");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_schema() -> anyhow::Result<()> {
+        let request = Request::get("/datasets/t/schema").body(Body::empty())?;
+        let response = router(state(None)?).oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body)?,
+            json!([
+                {"name": "id", "type": "Int64", "nullable": false},
+                {"name": "name", "type": "Utf8", "nullable": false},
+            ])
+        );
+
+        let request = Request::get("/datasets/missing/schema").body(Body::empty())?;
+        let response = router(state(None)?).oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[test]
+    fn test_accepted_encoding() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Encoding::accepted(&headers), Encoding::Json);
+
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("text/html, text/csv;q=0.9, */*"),
+        );
+        assert_eq!(Encoding::accepted(&headers), Encoding::Csv);
+    }
+}
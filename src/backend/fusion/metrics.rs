@@ -0,0 +1,204 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex, RwLock,
+};
+
+use datafusion::{
+    config::ConfigOptions,
+    error::Result,
+    execution::memory_pool::{MemoryConsumer, MemoryPool, MemoryReservation},
+    physical_optimizer::optimizer::PhysicalOptimizerRule,
+    physical_plan::ExecutionPlan,
+};
+
+use crate::backend::CommandStats;
+
+/// A memory pool that remembers the peak of reserved memory since the last reset. The pool it
+/// wraps can be replaced between queries to change the memory limit.
+#[derive(Debug)]
+pub(super) struct TrackingPool {
+    inner: RwLock<Arc<dyn MemoryPool>>,
+    // the consumers registered with the inner pool, which must unregister from the same one
+    consumers: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+/// Keeps the physical plans the session creates while enabled, i.e. with `timing` on, to read
+/// their metrics once they have run. They are cleared before each command.
+#[derive(Debug, Default)]
+pub(super) struct PlanRecorder {
+    plans: Mutex<Vec<Arc<dyn ExecutionPlan>>>,
+    enabled: AtomicBool,
+}
+
+impl TrackingPool {
+    pub fn new(inner: Arc<dyn MemoryPool>) -> Self {
+        Self {
+            inner: RwLock::new(inner),
+            consumers: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    /// Replace the inner pool, which fails while a query holds a reservation of the current one.
+    pub fn replace(&self, inner: Arc<dyn MemoryPool>) -> anyhow::Result<()> {
+        // registrations hold the read lock, so none can start until the pool is replaced
+        let mut current = self.inner.write().unwrap();
+        if self.consumers.load(Ordering::SeqCst) > 0 {
+            anyhow::bail!("the memory limit can't change while queries are running");
+        }
+        *current = inner;
+        Ok(())
+    }
+
+    fn inner(&self) -> Arc<dyn MemoryPool> {
+        self.inner.read().unwrap().clone()
+    }
+
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    pub fn reset_peak(&self) {
+        self.peak.store(self.reserved(), Ordering::Relaxed);
+    }
+
+    fn update_peak(&self) {
+        self.peak.fetch_max(self.reserved(), Ordering::Relaxed);
+    }
+}
+
+impl MemoryPool for TrackingPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        let inner = self.inner.read().unwrap();
+        inner.register(consumer);
+        self.consumers.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        let inner = self.inner.read().unwrap();
+        inner.unregister(consumer);
+        self.consumers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.inner().grow(reservation, additional);
+        self.update_peak();
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.inner().shrink(reservation, shrink)
+    }
+
+    fn try_grow(&self, reservation: &MemoryReservation, additional: usize) -> Result<()> {
+        self.inner().try_grow(reservation, additional)?;
+        self.update_peak();
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.inner().reserved()
+    }
+}
+
+impl PlanRecorder {
+    pub fn clear(&self) {
+        self.plans.lock().unwrap().clear();
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.clear();
+        }
+    }
+
+    /// Sum the rows returned and the bytes scanned by the recorded plans.
+    pub fn stats(&self, peak_memory: usize) -> CommandStats {
+        let plans = self.plans.lock().unwrap();
+        let mut stats = CommandStats {
+            peak_memory,
+            ..Default::default()
+        };
+        for plan in plans.iter() {
+            if let Some(rows) = output_rows(plan.as_ref()) {
+                *stats.rows.get_or_insert(0) += rows;
+            }
+            if let Some(bytes) = bytes_scanned(plan.as_ref()) {
+                *stats.bytes_scanned.get_or_insert(0) += bytes;
+            }
+        }
+        stats
+    }
+}
+
+impl PhysicalOptimizerRule for PlanRecorder {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if self.enabled.load(Ordering::Relaxed) {
+            self.plans.lock().unwrap().push(plan.clone());
+        }
+        Ok(plan)
+    }
+
+    fn name(&self) -> &str {
+        "plan_recorder"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+// the output of the topmost operator reporting it, plans that never ran have none
+fn output_rows(plan: &dyn ExecutionPlan) -> Option<u64> {
+    match plan.metrics().and_then(|m| m.output_rows()) {
+        Some(rows) => Some(rows as u64),
+        None => plan
+            .children()
+            .into_iter()
+            .find_map(|child| output_rows(child.as_ref())),
+    }
+}
+
+fn bytes_scanned(plan: &dyn ExecutionPlan) -> Option<u64> {
+    let own = plan
+        .metrics()
+        .and_then(|m| m.sum_by_name("bytes_scanned"))
+        .map(|v| v.as_usize() as u64);
+    plan.children()
+        .into_iter()
+        .filter_map(|child| bytes_scanned(child.as_ref()))
+        .chain(own)
+        .reduce(|a, b| a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::execution::memory_pool::{FairSpillPool, UnboundedMemoryPool};
+
+    use super::*;
+
+    #[test]
+    fn test_replace_pool() -> anyhow::Result<()> {
+        let tracking = Arc::new(TrackingPool::new(Arc::new(FairSpillPool::new(1024))));
+        let pool: Arc<dyn MemoryPool> = tracking.clone();
+        let mut reservation = MemoryConsumer::new("sort")
+            .with_can_spill(true)
+            .register(&pool);
+        reservation.try_grow(512)?;
+        assert_eq!(tracking.peak(), 512);
+        assert!(tracking
+            .replace(Arc::new(UnboundedMemoryPool::default()))
+            .is_err());
+
+        // the reservation is returned to the pool it was taken from
+        drop(reservation);
+        assert_eq!(tracking.reserved(), 0);
+        tracking.replace(Arc::new(UnboundedMemoryPool::default()))?;
+        Ok(())
+    }
+}
@@ -1,22 +1,386 @@
-use std::ops::Deref;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    ops::{Deref, Range},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use arrow::{array::RecordBatch, util::pretty::pretty_format_batches};
-use datafusion::prelude::{SessionConfig, SessionContext};
+use anyhow::{anyhow, bail};
+use arrow::{
+    array::{AsArray, RecordBatch},
+    compute::concat_batches,
+    datatypes::SchemaRef,
+};
+use datafusion::{
+    datasource::MemTable,
+    execution::{
+        context::SQLOptions,
+        disk_manager::{DiskManager, DiskManagerConfig},
+        memory_pool::{FairSpillPool, MemoryPool, UnboundedMemoryPool},
+        runtime_env::{RuntimeConfig, RuntimeEnv},
+        session_state::SessionState,
+    },
+    physical_plan::collect_partitioned,
+    prelude::{col, CsvReadOptions, DataFrame, NdJsonReadOptions, SessionConfig, SessionContext},
+};
+use futures::{future::try_join_all, FutureExt};
+use tokio::net::TcpListener;
 
-use crate::{Backend, ConnectOpts, DatasetConn, ReplDisplay};
+use crate::{
+    cli::FileOpts,
+    utils::{human_bytes, parse_bytes, sort_key, track_setting},
+    Backend, ConnectOpts, ConvertOpts, CorrOpts, CountsOpts, DatasetConn, DiffOpts, DupsOpts,
+    ReplDisplay, SampleOpts, ServeOpts,
+};
 
+use super::{
+    files::{codec_name, disk_usage, list_files, parquet_metadata},
+    output::format_batches,
+    CheckReport, CommandStats, DatasetInfo, DatasetSource, OutputOptions, ParquetInspection, Rule,
+    RuleResult,
+};
+
+mod check;
+mod convert;
+mod corr;
+mod counts;
 mod describe;
 mod df_describe;
-use self::describe::DataFrameDescriber;
+mod diff;
+mod dups;
+mod explain;
+mod flight;
+mod hist;
+mod http;
+mod metrics;
+mod pg;
+mod sample;
+mod settings;
+mod slice;
+use self::{
+    check::{check_rule, CHECK_REF_TABLE, CHECK_TABLE},
+    convert::write_dataframe,
+    corr::correlation,
+    counts::value_counts,
+    describe::{DataFrameDescriber, Description},
+    diff::DataDiff,
+    dups::Duplicates,
+    explain::QueryPlan,
+    hist::{sparklines, Histogram},
+    metrics::{PlanRecorder, TrackingPool},
+    sample::{sample_sql, Sample, SAMPLE_TABLE},
+    settings::{config_key, config_settings, settings_batch, Setting},
+    slice::{parquet_num_rows, read_parquet_rows, tail_rows},
+};
 
-pub struct DataFusionBackend(SessionContext);
+pub struct DataFusionBackend {
+    ctx: SessionContext,
+    datasets: BTreeMap<String, DatasetSource>,
+    pool: Arc<TrackingPool>,
+    recorder: Arc<PlanRecorder>,
+    timing: bool,
+    output: OutputOptions,
+    // the settings changed with `set`, in the order they were last set
+    changed: Vec<(String, String)>,
+    // `None` for no limit
+    memory_limit: Option<usize>,
+    // `None` for the OS temp dir
+    spill_dir: Option<String>,
+}
 
 impl DataFusionBackend {
     pub fn new() -> Self {
         let mut config = SessionConfig::new();
         config.options_mut().catalog.information_schema = true;
-        let ctx = SessionContext::new_with_config(config);
-        Self(ctx)
+        let pool = Arc::new(TrackingPool::new(memory_pool(None)));
+        let runtime = RuntimeEnv::new(RuntimeConfig::new().with_memory_pool(pool.clone()))
+            .expect("Failed to create runtime env");
+        let recorder = Arc::new(PlanRecorder::default());
+        let state = SessionState::new_with_config_rt(config, Arc::new(runtime))
+            .add_physical_optimizer_rule(recorder.clone());
+        Self {
+            ctx: SessionContext::new_with_state(state),
+            datasets: BTreeMap::new(),
+            pool,
+            recorder,
+            timing: false,
+            output: OutputOptions::default(),
+            changed: vec![],
+            memory_limit: None,
+            spill_dir: None,
+        }
+    }
+
+    fn set_memory_limit(&mut self, limit: Option<usize>) -> anyhow::Result<()> {
+        self.pool.replace(memory_pool(limit))?;
+        self.memory_limit = limit;
+        Ok(())
+    }
+
+    // the disk manager is fixed once the runtime is built, so rebuild the session around it
+    fn set_spill_dir(&mut self, dir: &str) -> anyhow::Result<()> {
+        fs::create_dir_all(dir)?;
+        let state = self.ctx.state();
+        let current = state.runtime_env().clone();
+        let runtime = RuntimeEnv {
+            memory_pool: current.memory_pool.clone(),
+            disk_manager: DiskManager::try_new(DiskManagerConfig::NewSpecified(vec![dir.into()]))?,
+            cache_manager: current.cache_manager.clone(),
+            object_store_registry: current.object_store_registry.clone(),
+        };
+        let state = SessionState::new_with_config_rt_and_catalog_list(
+            state.config().clone(),
+            Arc::new(runtime),
+            state.catalog_list().clone(),
+        )
+        .add_physical_optimizer_rule(self.recorder.clone());
+        self.ctx = SessionContext::new_with_state(state);
+        self.spill_dir = Some(dir.to_string());
+        Ok(())
+    }
+
+    // resolve a registered dataset, or read a local file directly
+    pub(crate) async fn dataframe(&self, target: &str) -> anyhow::Result<DataFrame> {
+        // paths like `data/foo.csv` fail to resolve as a table reference
+        if self.ctx.table_exist(target).unwrap_or(false) {
+            return Ok(self.ctx.table(target).await?);
+        }
+        let conn: DatasetConn = target.parse().map_err(|e: String| anyhow!(e))?;
+        let df = match &conn {
+            DatasetConn::Csv(file_opts) => {
+                self.ctx
+                    .read_csv(&file_opts.filename, csv_options(file_opts))
+                    .await?
+            }
+            DatasetConn::Parquet(filename) => {
+                self.ctx.read_parquet(filename, Default::default()).await?
+            }
+            DatasetConn::NdJson(file_opts) => {
+                self.ctx
+                    .read_json(&file_opts.filename, json_options(file_opts))
+                    .await?
+            }
+            DatasetConn::Postgres(_) => bail!("Postgres connection is not supported yet"),
+        };
+        Ok(df)
+    }
+
+    // the location of a parquet dataset, or a parquet file read directly
+    fn parquet_location(&self, target: &str) -> Option<String> {
+        match self.datasets.get(target) {
+            Some(DatasetSource::File(DatasetConn::Parquet(filename))) => Some(filename.clone()),
+            Some(_) => None,
+            None if self.ctx.table_exist(target).unwrap_or(false) => None,
+            None => match target.parse() {
+                Ok(DatasetConn::Parquet(filename)) => Some(filename),
+                _ => None,
+            },
+        }
+    }
+
+    async fn slice(
+        &self,
+        name: &str,
+        range: Range<usize>,
+        order_by: &[String],
+    ) -> anyhow::Result<DataFrame> {
+        if order_by.is_empty() {
+            if let Some(location) = self.parquet_location(name) {
+                let (schema, batches) = read_parquet_rows(&location, range)?;
+                let table = MemTable::try_new(schema, vec![batches])?;
+                return Ok(self.ctx.read_table(Arc::new(table))?);
+            }
+        }
+        let df = sorted(self.dataframe(name).await?, order_by)?;
+        Ok(df.limit(range.start, Some(range.len()))?)
+    }
+
+    async fn check_rules(&self, rules: &[Rule]) -> anyhow::Result<Vec<RuleResult>> {
+        let mut results = vec![];
+        for rule in rules {
+            if let Rule::ForeignKey { dataset, .. } = rule {
+                let parent = self.dataframe(dataset).await?;
+                self.ctx.deregister_table(CHECK_REF_TABLE)?;
+                self.ctx
+                    .register_table(CHECK_REF_TABLE, parent.into_view())?;
+            }
+            results.push(check_rule(&self.ctx, rule).await?);
+        }
+        Ok(results)
+    }
+
+    // taotie's own settings, besides the datafusion options
+    fn settings(&self) -> Vec<Setting> {
+        let output = &self.output;
+        let setting = |name: &str, value: String, description: &str| {
+            (name.to_string(), Some(value), description.to_string())
+        };
+        vec![
+            setting(
+                "timing",
+                if self.timing { "on" } else { "off" }.to_string(),
+                "Show the time and resources used after each command",
+            ),
+            setting(
+                "format",
+                output.format.to_string(),
+                "The format of query results: table, csv or json",
+            ),
+            setting(
+                "max_rows",
+                output
+                    .max_rows
+                    .map_or("none".to_string(), |v| v.to_string()),
+                "The max number of rows to show for query results",
+            ),
+            setting(
+                "memory_limit",
+                self.memory_limit
+                    .map_or("none".to_string(), |v| human_bytes(v as u64)),
+                "The max memory of queries, beyond which they spill to disk",
+            ),
+            setting(
+                "spill_dir",
+                self.spill_dir
+                    .clone()
+                    .unwrap_or_else(|| "os temp dir".to_string()),
+                "The directory of spill files",
+            ),
+        ]
+    }
+
+    // the registered datasets, counting the rows of those without metadata when `exact`
+    pub(crate) async fn dataset_infos(&self, exact: bool) -> anyhow::Result<Vec<DatasetInfo>> {
+        let sql = "select table_name, table_type from information_schema.tables where table_schema = 'public' order by table_name";
+        let batches = self.ctx.sql(sql).await?.collect().await?;
+
+        let mut infos = vec![];
+        for batch in batches.iter() {
+            let table_names = batch.column(0).as_string::<i32>();
+            let table_types = batch.column(1).as_string::<i32>();
+            for i in 0..batch.num_rows() {
+                let name = table_names.value(i);
+                let mut info = match self.datasets.get(name) {
+                    Some(source) => dataset_info(name, source),
+                    None => DatasetInfo::new(name, table_types.value(i).to_lowercase()),
+                };
+                if info.rows.is_none() && info.error.is_none() && exact {
+                    match self.count_rows(name).await {
+                        Ok(rows) => info.rows = Some(rows),
+                        Err(e) => info.error = Some(e.to_string()),
+                    }
+                }
+                infos.push(info);
+            }
+        }
+
+        Ok(infos)
+    }
+
+    async fn count_rows(&self, name: &str) -> anyhow::Result<u64> {
+        Ok(self.ctx.table(name).await?.count().await? as u64)
+    }
+
+    // the summary statistics of each column of a dataset
+    pub(crate) async fn describe_stats(&self, name: &str) -> anyhow::Result<DataFrame> {
+        let df = self.ctx.sql(&format!("select * from {}", name)).await?;
+        DataFrameDescriber::try_new(df)?.describe().await
+    }
+
+    fn ensure_new(&self, name: &str, replace: bool) -> anyhow::Result<()> {
+        if !replace && self.ctx.table_exist(name)? {
+            bail!(
+                "dataset already exists: {}, use --replace to replace it",
+                name
+            );
+        }
+        Ok(())
+    }
+
+    fn register_view(
+        &mut self,
+        name: &str,
+        df: DataFrame,
+        definition: impl Into<String>,
+    ) -> anyhow::Result<()> {
+        self.ctx.deregister_table(name)?;
+        self.ctx.register_table(name, df.into_view())?;
+        self.track_dataset(name, DatasetSource::View(definition.into()));
+        Ok(())
+    }
+
+    async fn cache_dataframe(
+        &mut self,
+        name: &str,
+        df: DataFrame,
+        definition: impl Into<String>,
+    ) -> anyhow::Result<usize> {
+        let plan = df.create_physical_plan().await?;
+        let schema = plan.schema();
+        let partitions = collect_partitioned(plan, self.ctx.task_ctx()).await?;
+        let rows = partitions.iter().flatten().map(|b| b.num_rows()).sum();
+        let size = partitions
+            .iter()
+            .flatten()
+            .map(|batch| batch.get_array_memory_size())
+            .sum();
+        let table = MemTable::try_new(schema, partitions)?;
+        self.ctx.deregister_table(name)?;
+        self.ctx.register_table(name, Arc::new(table))?;
+        self.track_dataset(
+            name,
+            DatasetSource::Memory {
+                sql: definition.into(),
+                rows,
+                size,
+            },
+        );
+        Ok(size)
+    }
+
+    fn track_dataset(&mut self, name: &str, source: DatasetSource) {
+        self.datasets.insert(name.to_string(), source);
+    }
+
+    fn apply_setting(&mut self, name: &str, value: &str) -> anyhow::Result<()> {
+        if self.output.set(name, value)? {
+            return Ok(());
+        }
+        match name {
+            "timing" => {
+                self.timing = parse_switch(value)?;
+                self.recorder.set_enabled(self.timing);
+            }
+            "memory_limit" => match value {
+                "none" | "0" => self.set_memory_limit(None)?,
+                v => self.set_memory_limit(Some(parse_bytes(v)?))?,
+            },
+            "spill_dir" => self.set_spill_dir(value)?,
+            name => {
+                let state = self.ctx.state_ref();
+                let mut state = state.write();
+                let options = state.config_mut().options_mut();
+                let key = config_key(options, name)?;
+                options.set(&key, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    // the catalog and schema the datasets are registered in
+    pub(crate) fn default_schema(&self) -> (String, String) {
+        let config = self.ctx.copied_config();
+        let catalog = &config.options().catalog;
+        (
+            catalog.default_catalog.clone(),
+            catalog.default_schema.clone(),
+        )
+    }
+
+    // a query of a client of the servers, which can't change the datasets or the session
+    pub(crate) async fn read_only_sql(&self, sql: &str) -> anyhow::Result<DataFrame> {
+        Ok(self.ctx.sql_with_options(sql, read_only()).await?)
     }
 }
 
@@ -24,11 +388,9 @@ impl Backend for DataFusionBackend {
     type DataFrame = datafusion::dataframe::DataFrame;
     async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()> {
         match &opts.conn {
-            DatasetConn::Postgres(_conn_str) => {
-                println!("Postgres connection is not supported yet")
-            }
+            DatasetConn::Postgres(_conn_str) => bail!("Postgres connection is not supported yet"),
             DatasetConn::Csv(file_opts) => {
-                self.register_csv(&opts.name, &file_opts.filename, Default::default())
+                self.register_csv(&opts.name, &file_opts.filename, csv_options(file_opts))
                     .await?;
             }
             DatasetConn::Parquet(filename) => {
@@ -36,42 +398,429 @@ impl Backend for DataFusionBackend {
                     .await?;
             }
             DatasetConn::NdJson(file_opts) => {
-                self.register_json(&opts.name, &file_opts.filename, Default::default())
+                self.register_json(&opts.name, &file_opts.filename, json_options(file_opts))
                     .await?;
             }
         }
+        self.track_dataset(&opts.name, DatasetSource::File(opts.conn.clone()));
         Ok(())
     }
 
-    async fn list(&self) -> anyhow::Result<impl ReplDisplay> {
-        let sql = "select table_name, table_type from information_schema.tables where table_schema = 'public'";
-        let df = self.0.sql(sql).await?;
-        Ok(df)
+    async fn list(&self, exact: bool) -> anyhow::Result<impl ReplDisplay> {
+        let infos = self.dataset_infos(exact).await?;
+        DatasetInfo::to_record_batch(&infos)
     }
 
-    async fn schema(&self, name: &str) -> anyhow::Result<impl ReplDisplay> {
-        let df = self.0.sql(&format!("DESCRIBE {}", name)).await?;
-        Ok(df)
+    async fn schema(&self, name: &str) -> anyhow::Result<SchemaRef> {
+        let df = self.dataframe(name).await?;
+        Ok(Arc::new(df.schema().as_arrow().clone()))
     }
 
-    async fn describe(&self, name: &str) -> anyhow::Result<impl ReplDisplay> {
-        let df = self.0.sql(&format!("select * from {}", name)).await?;
-        let ddf = DataFrameDescriber::try_new(df)?;
-        ddf.describe().await
+    async fn describe(&self, name: &str, sparkline: bool) -> anyhow::Result<impl ReplDisplay> {
+        let stats = self.describe_stats(name).await?;
+        let sparklines = match sparkline {
+            true => {
+                let df = self.ctx.sql(&format!("select * from {}", name)).await?;
+                Some(sparklines(&df).await?)
+            }
+            false => None,
+        };
+        Ok(Description { stats, sparklines })
     }
 
     async fn head(&self, name: &str, size: usize) -> anyhow::Result<impl ReplDisplay> {
         let df = self
-            .0
+            .ctx
             .sql(&format!("SELECT * FROM {} LIMIT {}", name, size))
             .await?;
         Ok(df)
     }
 
+    async fn tail(
+        &self,
+        name: &str,
+        size: usize,
+        order_by: &[String],
+    ) -> anyhow::Result<impl ReplDisplay> {
+        if let Some(location) = self.parquet_location(name) {
+            if order_by.is_empty() {
+                let total = parquet_num_rows(&location)?;
+                return self
+                    .slice(name, total.saturating_sub(size)..total, order_by)
+                    .await;
+            }
+        }
+        let df = sorted(self.dataframe(name).await?, order_by)?;
+        let (schema, batches) = tail_rows(df, size).await?;
+        let table = MemTable::try_new(schema, vec![batches])?;
+        Ok(self.ctx.read_table(Arc::new(table))?)
+    }
+
+    async fn rows(
+        &self,
+        name: &str,
+        range: Range<usize>,
+        order_by: &[String],
+    ) -> anyhow::Result<impl ReplDisplay> {
+        self.slice(name, range, order_by).await
+    }
+
     async fn sql(&self, sql: &str) -> anyhow::Result<impl ReplDisplay> {
-        let df = self.0.sql(sql).await?;
+        let df = self.ctx.sql(sql).await?;
         Ok(df)
     }
+
+    async fn collect(&self, sql: &str) -> anyhow::Result<RecordBatch> {
+        let df = self.ctx.sql(sql).await?;
+        let schema = Arc::new(df.schema().as_arrow().clone());
+        let batches = df.collect().await?;
+        Ok(concat_batches(&schema, &batches)?)
+    }
+
+    async fn explain(&self, sql: &str, analyze: bool) -> anyhow::Result<impl ReplDisplay> {
+        let df = self.ctx.sql(sql).await?;
+        QueryPlan::try_new(&self.ctx, df, analyze).await
+    }
+
+    async fn create_view(&mut self, name: &str, sql: &str, replace: bool) -> anyhow::Result<()> {
+        self.ensure_new(name, replace)?;
+        let df = self.ctx.sql(sql).await?;
+        self.register_view(name, df, sql)
+    }
+
+    async fn cache(&mut self, name: &str, sql: &str, replace: bool) -> anyhow::Result<usize> {
+        self.ensure_new(name, replace)?;
+        let df = self.ctx.sql(sql).await?;
+        self.cache_dataframe(name, df, sql).await
+    }
+
+    async fn deregister(&mut self, name: &str) -> anyhow::Result<()> {
+        self.ctx
+            .deregister_table(name)?
+            .ok_or_else(|| anyhow!("dataset not found: {}", name))?;
+        self.datasets.remove(name);
+        Ok(())
+    }
+
+    async fn inspect(&self, target: &str) -> anyhow::Result<impl ReplDisplay> {
+        let location = match self.datasets.get(target) {
+            Some(DatasetSource::File(DatasetConn::Parquet(filename))) => filename.as_str(),
+            Some(source) => bail!(
+                "{} is a {} dataset, only parquet files can be inspected",
+                target,
+                source.kind()
+            ),
+            None => target,
+        };
+        ParquetInspection::try_new(location)
+    }
+
+    async fn convert(&self, opts: &ConvertOpts) -> anyhow::Result<u64> {
+        let df = self.dataframe(&opts.src).await?;
+        write_dataframe(df, opts).await
+    }
+
+    async fn diff(&mut self, opts: &DiffOpts) -> anyhow::Result<impl ReplDisplay> {
+        let a = self.dataframe(&opts.a).await?;
+        let b = self.dataframe(&opts.b).await?;
+        let diff = DataDiff::try_new(a, b, &opts.key, opts.sample).await?;
+        if let Some(name) = &opts.into {
+            self.register_view(
+                name,
+                diff.diff.clone(),
+                format!("diff {} {}", opts.a, opts.b),
+            )?;
+        }
+        Ok(diff)
+    }
+
+    async fn dups(&mut self, opts: &DupsOpts) -> anyhow::Result<impl ReplDisplay> {
+        let df = self.dataframe(&opts.name).await?;
+        let mut dups = Duplicates::try_new(&self.ctx, df, opts).await?;
+        if let (Some(name), Some(dedup)) = (&opts.into, dups.dedup.take()) {
+            self.register_view(name, dedup, format!("dups {}", opts.name))?;
+        }
+        Ok(dups)
+    }
+
+    async fn counts(&self, opts: &CountsOpts) -> anyhow::Result<impl ReplDisplay> {
+        let df = self.dataframe(&opts.name).await?;
+        value_counts(df, &opts.columns, opts.top, opts.normalize).await
+    }
+
+    async fn hist(
+        &self,
+        name: &str,
+        column: &str,
+        bins: usize,
+    ) -> anyhow::Result<impl ReplDisplay> {
+        let df = self.dataframe(name).await?;
+        Histogram::try_new(df, column, bins).await
+    }
+
+    async fn corr(&self, opts: &CorrOpts) -> anyhow::Result<impl ReplDisplay> {
+        let df = self.dataframe(&opts.name).await?;
+        correlation(&self.ctx, df, &opts.columns, opts.method).await
+    }
+
+    async fn check(&self, name: &str, rules: &[Rule]) -> anyhow::Result<CheckReport> {
+        let df = self.dataframe(name).await?;
+        self.ctx.register_table(CHECK_TABLE, df.into_view())?;
+        let results = self.check_rules(rules).await;
+        self.ctx.deregister_table(CHECK_TABLE)?;
+        self.ctx.deregister_table(CHECK_REF_TABLE)?;
+        Ok(CheckReport::new(results?))
+    }
+
+    async fn set(&mut self, name: &str, value: &str) -> anyhow::Result<Option<String>> {
+        self.apply_setting(name, value)?;
+        track_setting(&mut self.changed, name, value);
+        Ok(None)
+    }
+
+    fn output(&self) -> OutputOptions {
+        self.output
+    }
+
+    fn changed_settings(&self) -> Vec<(String, String)> {
+        self.changed.clone()
+    }
+
+    async fn show(&self, name: &str) -> anyhow::Result<impl ReplDisplay> {
+        let mut settings = self.settings();
+        let options = self.ctx.state().config_options().clone();
+        match name {
+            "all" => settings.extend(config_settings(&options)),
+            name if settings.iter().any(|(n, _, _)| n == name) => {
+                settings.retain(|(n, _, _)| n == name)
+            }
+            name => {
+                let key = config_key(&options, name)?;
+                settings = config_settings(&options);
+                settings.retain(|(n, _, _)| *n == key);
+            }
+        }
+        settings_batch(settings)
+    }
+
+    fn reset_stats(&mut self) {
+        self.recorder.clear();
+        self.pool.reset_peak();
+    }
+
+    fn stats(&self) -> Option<CommandStats> {
+        self.timing.then(|| self.recorder.stats(self.pool.peak()))
+    }
+
+    async fn serve(&self, opts: &ServeOpts) -> anyhow::Result<()> {
+        // the queries of clients don't count towards the memory and stats of the REPL
+        let backend = Arc::new(self.snapshot().await?);
+        let mut servers = vec![];
+        if let Some(addr) = &opts.pg {
+            let listener = listener(addr, &opts.listeners.pg).await?;
+            servers.push(pg::serve(backend.ctx.clone(), listener).boxed_local());
+        }
+        if let Some(addr) = &opts.flight {
+            let listener = listener(addr, &opts.listeners.flight).await?;
+            servers.push(flight::serve(backend.clone(), listener).boxed_local());
+        }
+        if let Some(addr) = &opts.http {
+            let listener = listener(addr, &opts.listeners.http).await?;
+            let max_rows = backend.output.max_rows;
+            servers.push(http::serve(backend.ctx.clone(), max_rows, listener).boxed_local());
+        }
+        try_join_all(servers).await?;
+        Ok(())
+    }
+
+    // tables are shared, but registering or dropping them doesn't affect the other backend, and
+    // the memory and stats of its queries are its own
+    async fn snapshot(&self) -> anyhow::Result<Self> {
+        let state = self.ctx.state();
+        let catalog = &state.config_options().catalog;
+        let schema = self
+            .ctx
+            .catalog(&catalog.default_catalog)
+            .and_then(|c| c.schema(&catalog.default_schema))
+            .ok_or_else(|| anyhow!("default schema not found"))?;
+
+        let current = state.runtime_env();
+        let pool = Arc::new(TrackingPool::new(memory_pool(self.memory_limit)));
+        let recorder = Arc::new(PlanRecorder::default());
+        let runtime = RuntimeEnv {
+            memory_pool: pool.clone(),
+            disk_manager: current.disk_manager.clone(),
+            cache_manager: current.cache_manager.clone(),
+            object_store_registry: current.object_store_registry.clone(),
+        };
+        let state = SessionState::new_with_config_rt(state.config().clone(), Arc::new(runtime))
+            .add_physical_optimizer_rule(recorder.clone());
+        let ctx = SessionContext::new_with_state(state);
+        for name in schema.table_names() {
+            if let Some(table) = schema.table(&name).await? {
+                ctx.register_table(name.as_str(), table)?;
+            }
+        }
+        Ok(Self {
+            ctx,
+            datasets: self.datasets.clone(),
+            pool,
+            recorder,
+            timing: false,
+            output: self.output,
+            changed: self.changed.clone(),
+            memory_limit: self.memory_limit,
+            spill_dir: self.spill_dir.clone(),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "datafusion"
+    }
+
+    fn catalog(&self) -> Vec<(String, DatasetSource)> {
+        self.datasets
+            .iter()
+            .map(|(name, source)| (name.clone(), source.clone()))
+            .collect()
+    }
+
+    async fn sample(&mut self, opts: &SampleOpts) -> anyhow::Result<impl ReplDisplay> {
+        let df = self.dataframe(&opts.name).await?;
+        let schema = df.schema().as_arrow().clone();
+        let (seed, random) = match opts.seed {
+            Some(seed) => (seed, false),
+            None => (
+                SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+                true,
+            ),
+        };
+        let sql = sample_sql(&schema, opts, seed)?;
+
+        self.ctx.register_table(SAMPLE_TABLE, df.into_view())?;
+        let sample = self.ctx.sql(&sql).await;
+        self.ctx.deregister_table(SAMPLE_TABLE)?;
+        let sample = sample?;
+
+        let rows = match &opts.into {
+            Some(name) => {
+                self.cache_dataframe(name, sample, format!("sample {}", opts.name))
+                    .await?;
+                self.ctx.table(name.as_str()).await?
+            }
+            None => sample,
+        };
+        Ok(Sample {
+            rows,
+            seed: random.then_some(seed),
+        })
+    }
+}
+
+// sorts, joins and aggregations spill to disk once the pool is exhausted
+fn memory_pool(limit: Option<usize>) -> Arc<dyn MemoryPool> {
+    match limit {
+        Some(limit) => Arc::new(FairSpillPool::new(limit)),
+        None => Arc::new(UnboundedMemoryPool::default()),
+    }
+}
+
+/// Only queries, no DDL, DML or `SET`, for the clients of the servers.
+pub(crate) fn read_only() -> SQLOptions {
+    SQLOptions::new()
+        .with_allow_ddl(false)
+        .with_allow_dml(false)
+        .with_allow_statements(false)
+}
+
+// the listener bound by the `serve` command, or bind the address, e.g. for `bg serve`
+async fn listener(
+    addr: &str,
+    bound: &Option<std::net::TcpListener>,
+) -> anyhow::Result<TcpListener> {
+    match bound {
+        Some(listener) => Ok(TcpListener::from_std(listener.try_clone()?)?),
+        None => Ok(TcpListener::bind(addr).await?),
+    }
+}
+
+fn csv_options(file_opts: &FileOpts) -> CsvReadOptions<'_> {
+    CsvReadOptions::new()
+        .file_extension(file_opts.file_extension())
+        .file_compression_type(file_opts.compression)
+}
+
+fn json_options(file_opts: &FileOpts) -> NdJsonReadOptions<'_> {
+    NdJsonReadOptions::default()
+        .file_extension(file_opts.file_extension())
+        .file_compression_type(file_opts.compression)
+}
+
+fn parse_switch(value: &str) -> anyhow::Result<bool> {
+    match value {
+        "on" | "true" => Ok(true),
+        "off" | "false" => Ok(false),
+        v => bail!("expect on or off, got {}", v),
+    }
+}
+
+// `col[:asc|desc]`, ascending by default
+fn sorted(df: DataFrame, sort_by: &[String]) -> anyhow::Result<DataFrame> {
+    if sort_by.is_empty() {
+        return Ok(df);
+    }
+    let exprs = sort_by
+        .iter()
+        .map(|v| {
+            let (name, descending) = sort_key(v);
+            col(name).sort(!descending, false)
+        })
+        .collect();
+    Ok(df.sort(exprs)?)
+}
+
+// an error reading the files of a dataset goes in its row instead of failing the whole list
+fn dataset_info(name: &str, source: &DatasetSource) -> DatasetInfo {
+    let mut info = DatasetInfo::new(name, source.kind());
+    info.source = Some(source.source().to_string());
+    match source {
+        DatasetSource::File(conn) => {
+            info.format = Some(conn.format().to_string());
+            info.compression = conn.compression().map(|c| c.to_string());
+            if let Err(e) = file_info(&mut info, conn) {
+                info.error = Some(e.to_string());
+            }
+        }
+        DatasetSource::View(_) => {}
+        DatasetSource::Memory { rows, size, .. } => {
+            info.rows = Some(*rows as u64);
+            info.memory = Some(*size);
+        }
+    }
+    info
+}
+
+fn file_info(info: &mut DatasetInfo, conn: &DatasetConn) -> anyhow::Result<()> {
+    if let DatasetConn::Postgres(_) = conn {
+        return Ok(());
+    }
+    let files = list_files(conn.location())?;
+    info.files = Some(files.len() as u64);
+    info.size = Some(disk_usage(&files)?);
+    if let DatasetConn::Parquet(_) = conn {
+        let mut rows = 0;
+        let mut codecs = BTreeSet::new();
+        for file in files.iter() {
+            let metadata = parquet_metadata(file)?;
+            rows += metadata.file_metadata().num_rows() as u64;
+            for rg in metadata.row_groups() {
+                codecs.extend(rg.columns().iter().map(|c| codec_name(c.compression())));
+            }
+        }
+        info.rows = Some(rows);
+        info.compression = Some(codecs.into_iter().collect::<Vec<_>>().join(","));
+    }
+    Ok(())
 }
 
 impl Default for DataFusionBackend {
@@ -84,29 +833,198 @@ impl Deref for DataFusionBackend {
     type Target = SessionContext;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.ctx
     }
 }
 
 impl ReplDisplay for datafusion::dataframe::DataFrame {
-    async fn display(self) -> anyhow::Result<String> {
-        let batches = self.collect().await?;
-        let data = pretty_format_batches(&batches)?;
-        Ok(data.to_string())
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let df = match output.max_rows {
+            Some(max_rows) => self.limit(0, Some(max_rows))?,
+            None => self,
+        };
+        let batches = df.collect().await?;
+        format_batches(&batches, &output)
     }
 }
 
 impl ReplDisplay for RecordBatch {
-    async fn display(self) -> anyhow::Result<String> {
-        let data = pretty_format_batches(&[self])?;
-        Ok(data.to_string())
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        format_batches(&[self], &output)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use arrow::{
+        array::{ArrayRef, AsArray, Int64Array},
+        datatypes::Int64Type,
+    };
+
+    use super::*;
+    use crate::{
+        backend::OutputFormat,
+        test_utils::{connect_opts, TestDir},
+    };
+
     #[tokio::test]
     async fn datafusion_backend() -> anyhow::Result<()> {
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_snapshot() -> anyhow::Result<()> {
+        let mut backend = DataFusionBackend::new();
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])?;
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+        backend.register_table("t", Arc::new(table))?;
+
+        // only the plans of a session with timing on are kept
+        backend.collect("select * from t").await?;
+        assert_eq!(backend.recorder.stats(0).rows, None);
+
+        let mut snapshot = backend.snapshot().await?;
+        assert!(!Arc::ptr_eq(&backend.pool, &snapshot.pool));
+        snapshot.collect("select * from t").await?;
+        assert_eq!(snapshot.recorder.stats(0).rows, None);
+        snapshot.set("timing", "on").await?;
+        snapshot.collect("select * from t where id > 1").await?;
+        assert_eq!(snapshot.recorder.stats(0).rows, Some(2));
+        assert_eq!(backend.recorder.stats(0).rows, None);
+
+        // the stats of the foreground don't reset those of a job
+        backend.reset_stats();
+        assert_eq!(snapshot.recorder.stats(0).rows, Some(2));
+
+        snapshot.deregister_table("t")?;
+        assert!(backend.table_exist("t")?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_output_options() -> anyhow::Result<()> {
+        let mut backend = DataFusionBackend::new();
+        backend.set("max_rows", "2").await?;
+        backend.set("format", "csv").await?;
+
+        let mut snapshot = backend.snapshot().await?;
+        assert_eq!(snapshot.output(), backend.output());
+        snapshot.set("format", "json").await?;
+        assert_eq!(backend.output().format, OutputFormat::Csv);
+
+        let df = backend
+            .sql("select * from (values (1), (2), (3)) as t(a)")
+            .await?;
+        assert_eq!(df.display(backend.output()).await?, "a\n1\n2\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_views_and_caches() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let csv = dir.write("t.csv", "id\n1\n2\n3\n");
+        let mut backend = DataFusionBackend::new();
+        backend.connect(&connect_opts("t", &csv)).await?;
+        backend
+            .create_view("v", "select * from t where id > 1", false)
+            .await?;
+        backend
+            .cache("c", "select * from v where id > 2", false)
+            .await?;
+
+        let infos = backend.dataset_infos(false).await?;
+        let kinds: Vec<_> = infos
+            .iter()
+            .map(|i| (i.name.as_str(), i.kind.as_str(), i.rows))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("c", "memory", Some(1)),
+                ("t", "file", None),
+                ("v", "view", None)
+            ]
+        );
+        let batch = backend.collect("select count(*) from v").await?;
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(0), 2);
+        let batch = backend.collect("select id from c").await?;
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(0), 3);
+
+        // an existing name, even of a connected file, is only replaced on request
+        let err = backend.create_view("t", "select 1", false).await;
+        assert!(err.unwrap_err().to_string().contains("--replace"));
+        assert!(backend.cache("v", "select 1", false).await.is_err());
+        backend.create_view("v", "select * from t", true).await?;
+        let batch = backend.collect("select count(*) from v").await?;
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(0), 3);
+
+        backend.deregister("c").await?;
+        assert!(backend.collect("select * from c").await.is_err());
+        assert!(backend.deregister("c").await.is_err());
+        assert_eq!(backend.dataset_infos(false).await?.len(), 2);
+        Ok(())
+    }
+
+    // the ids of the rows, one per line
+    async fn ids(rows: impl ReplDisplay) -> anyhow::Result<String> {
+        let output = OutputOptions {
+            format: OutputFormat::Csv,
+            max_rows: None,
+        };
+        let csv = rows.display(output).await?;
+        Ok(csv.trim_start_matches("id\n").trim_end().replace('\n', ","))
+    }
+
+    #[tokio::test]
+    async fn test_tail_and_rows() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let csv = dir.write("t.csv", "id\n1\n2\n3\n4\n5\n");
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5])) as ArrayRef,
+        )])?;
+        let parquet = dir.write_parquet("t.parquet", &batch, 2)?;
+        let mut backend = DataFusionBackend::new();
+        backend.connect(&connect_opts("c", &csv)).await?;
+        backend.connect(&connect_opts("p", &parquet)).await?;
+        let desc = ["id:desc".to_string()];
+
+        for name in ["c", "p"] {
+            assert_eq!(ids(backend.tail(name, 2, &[]).await?).await?, "4,5");
+            assert_eq!(ids(backend.tail(name, 9, &[]).await?).await?, "1,2,3,4,5");
+            assert_eq!(ids(backend.tail(name, 2, &desc).await?).await?, "2,1");
+            assert_eq!(ids(backend.rows(name, 1..3, &[]).await?).await?, "2,3");
+            assert_eq!(ids(backend.rows(name, 0..2, &desc).await?).await?, "5,4");
+            // a range past the end is cut short, or empty
+            assert_eq!(ids(backend.rows(name, 3..10, &[]).await?).await?, "4,5");
+            assert_eq!(ids(backend.rows(name, 7..9, &[]).await?).await?, "");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dataset_infos_error() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let csv = dir.write("a.csv", "id\n1\n2\n");
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![1])) as ArrayRef,
+        )])?;
+        let parquet = dir.write_parquet("b.parquet", &batch, 1024)?;
+
+        let mut backend = DataFusionBackend::new();
+        backend.connect(&connect_opts("a", &csv)).await?;
+        backend.connect(&connect_opts("b", &parquet)).await?;
+        std::fs::remove_file(&parquet)?;
+
+        let infos = backend.dataset_infos(true).await?;
+        assert_eq!(infos.len(), 2);
+        assert_eq!((infos[0].rows, infos[0].error.as_ref()), (Some(2), None));
+        assert!(infos[1].error.is_some());
+        assert_eq!(infos[1].rows, None);
+        Ok(())
+    }
 }
@@ -0,0 +1,465 @@
+use std::{fmt::Display, sync::Arc};
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray, RecordBatch, StringArray},
+    compute::cast,
+    datatypes::{
+        DataType, Date32Type, Fields, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+        TimeUnit, TimestampMicrosecondType,
+    },
+    util::display::{ArrayFormatter, FormatOptions},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use datafusion::{
+    common::ScalarValue,
+    logical_expr::{LogicalPlan, Statement},
+    prelude::SessionContext,
+    sql::parser::DFParser,
+};
+use futures::{stream, StreamExt};
+use pgwire::{
+    api::{
+        auth::noop::NoopStartupHandler,
+        portal::{Format, Portal},
+        query::{ExtendedQueryHandler, SimpleQueryHandler},
+        results::{
+            DataRowEncoder, DescribePortalResponse, DescribeStatementResponse, FieldInfo,
+            QueryResponse, Response, Tag,
+        },
+        stmt::{QueryParser, StoredStatement},
+        ClientInfo, Type,
+    },
+    error::{ErrorInfo, PgWireError, PgWireResult},
+    messages::data::DataRow,
+    tokio::process_socket,
+};
+use tokio::net::TcpListener;
+
+use super::read_only;
+
+/// Serve the tables of the session over the Postgres wire protocol until the process stops.
+/// Queries are read only, and `SET` statements of clients are acknowledged but ignored.
+/// Portals return all their rows at once, fetching them in chunks is rejected.
+pub async fn serve(ctx: SessionContext, listener: TcpListener) -> anyhow::Result<()> {
+    let startup = Arc::new(NoopStartupHandler);
+    let session = Arc::new(PgSession::new(ctx));
+    loop {
+        let (socket, _) = listener.accept().await?;
+        let (startup, session) = (startup.clone(), session.clone());
+        tokio::spawn(async move {
+            if let Err(e) = process_socket(socket, None, startup, session.clone(), session).await {
+                eprintln!("Postgres connection error: {}", e);
+            }
+        });
+    }
+}
+
+struct PgSession {
+    ctx: SessionContext,
+    parser: Arc<PlanParser>,
+}
+
+struct PlanParser {
+    ctx: SessionContext,
+}
+
+impl PgSession {
+    fn new(ctx: SessionContext) -> Self {
+        let parser = Arc::new(PlanParser { ctx: ctx.clone() });
+        Self { ctx, parser }
+    }
+
+    // rows stream batch by batch
+    async fn execute<'a>(&self, plan: LogicalPlan, format: &Format) -> PgWireResult<Response<'a>> {
+        // clients set their own options on connect, e.g. `SET extra_float_digits = 3`
+        if let LogicalPlan::Statement(Statement::SetVariable(_)) = plan {
+            return Ok(Response::Execution(Tag::new("SET")));
+        }
+        let df = self
+            .ctx
+            .execute_logical_plan(plan)
+            .await
+            .map_err(user_error)?;
+        let fields = Arc::new(field_infos(df.schema().fields(), format));
+        let batches = df.execute_stream().await.map_err(user_error)?;
+        let encoder = fields.clone();
+        let rows = batches
+            .map(move |batch| {
+                let rows = batch
+                    .map_err(user_error)
+                    .and_then(|batch| encode_rows(&encoder, &batch));
+                match rows {
+                    Ok(rows) => stream::iter(rows.into_iter().map(Ok).collect::<Vec<_>>()),
+                    Err(e) => stream::iter(vec![Err(e)]),
+                }
+            })
+            .flatten();
+        Ok(Response::Query(QueryResponse::new(fields, rows)))
+    }
+}
+
+#[async_trait]
+impl SimpleQueryHandler for PgSession {
+    async fn do_query<'a, 'b: 'a, C>(
+        &'b self,
+        _client: &mut C,
+        query: &'a str,
+    ) -> PgWireResult<Vec<Response<'a>>>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        let statements = DFParser::parse_sql(query).map_err(user_error)?;
+        if statements.is_empty() {
+            return Ok(vec![Response::EmptyQuery]);
+        }
+        let mut responses = vec![];
+        for statement in statements {
+            let plan = self
+                .ctx
+                .state()
+                .statement_to_plan(statement)
+                .await
+                .map_err(user_error)?;
+            verify(&plan)?;
+            responses.push(self.execute(plan, &Format::UnifiedText).await?);
+        }
+        Ok(responses)
+    }
+}
+
+#[async_trait]
+impl QueryParser for PlanParser {
+    type Statement = LogicalPlan;
+
+    async fn parse_sql(&self, sql: &str, _types: &[Type]) -> PgWireResult<Self::Statement> {
+        let plan = self
+            .ctx
+            .state()
+            .create_logical_plan(sql)
+            .await
+            .map_err(user_error)?;
+        verify(&plan)?;
+        Ok(plan)
+    }
+}
+
+#[async_trait]
+impl ExtendedQueryHandler for PgSession {
+    type Statement = LogicalPlan;
+    type QueryParser = PlanParser;
+
+    fn query_parser(&self) -> Arc<Self::QueryParser> {
+        self.parser.clone()
+    }
+
+    async fn do_query<'a, 'b: 'a, C>(
+        &'b self,
+        _client: &mut C,
+        portal: &'a Portal<Self::Statement>,
+        max_rows: usize,
+    ) -> PgWireResult<Response<'a>>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        // a portal runs to completion, there is no suspending it to fetch the rest later
+        if max_rows > 0 {
+            return Err(user_error(format!(
+                "fetching {} rows at a time is not supported, fetch all rows",
+                max_rows
+            )));
+        }
+        let plan = &portal.statement.statement;
+        let values = parameter_types(plan)?
+            .iter()
+            .enumerate()
+            .map(|(i, data_type)| parameter(portal, i, data_type))
+            .collect::<PgWireResult<Vec<_>>>()?;
+        let plan = plan.clone().with_param_values(values).map_err(user_error)?;
+        self.execute(plan, &portal.result_column_format).await
+    }
+
+    async fn do_describe_statement<C>(
+        &self,
+        _client: &mut C,
+        target: &StoredStatement<Self::Statement>,
+    ) -> PgWireResult<DescribeStatementResponse>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        let plan = &target.statement;
+        let params = parameter_types(plan)?
+            .iter()
+            .map(|data_type| pg_type(data_type).0)
+            .collect();
+        let fields = field_infos(plan.schema().fields(), &Format::UnifiedText);
+        Ok(DescribeStatementResponse::new(params, fields))
+    }
+
+    async fn do_describe_portal<C>(
+        &self,
+        _client: &mut C,
+        portal: &Portal<Self::Statement>,
+    ) -> PgWireResult<DescribePortalResponse>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        let fields = field_infos(
+            portal.statement.statement.schema().fields(),
+            &portal.result_column_format,
+        );
+        Ok(DescribePortalResponse::new(fields))
+    }
+}
+
+// the queries of the other servers, and `SET`, which `execute` acknowledges without running
+fn verify(plan: &LogicalPlan) -> PgWireResult<()> {
+    if let LogicalPlan::Statement(Statement::SetVariable(_)) = plan {
+        return Ok(());
+    }
+    read_only().verify_plan(plan).map_err(user_error)
+}
+
+fn user_error(e: impl Display) -> PgWireError {
+    PgWireError::UserError(Box::new(ErrorInfo::new(
+        "ERROR".to_string(),
+        "XX000".to_string(),
+        e.to_string(),
+    )))
+}
+
+// the Postgres type of an arrow type, and the arrow type its values are encoded from
+fn pg_type(data_type: &DataType) -> (Type, DataType) {
+    match data_type {
+        DataType::Boolean => (Type::BOOL, DataType::Boolean),
+        DataType::Int8 | DataType::Int16 | DataType::UInt8 => (Type::INT2, DataType::Int16),
+        DataType::Int32 | DataType::UInt16 => (Type::INT4, DataType::Int32),
+        DataType::Int64 | DataType::UInt32 => (Type::INT8, DataType::Int64),
+        DataType::Float16 | DataType::Float32 => (Type::FLOAT4, DataType::Float32),
+        DataType::Float64 => (Type::FLOAT8, DataType::Float64),
+        DataType::Date32 | DataType::Date64 => (Type::DATE, DataType::Date32),
+        DataType::Timestamp(_, None) => (
+            Type::TIMESTAMP,
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+        ),
+        DataType::Timestamp(_, Some(_)) => (
+            Type::TIMESTAMPTZ,
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        ),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+            (Type::VARCHAR, DataType::Utf8)
+        }
+        // formatted as text, e.g. u64, decimals, intervals and nested types
+        _ => (Type::TEXT, DataType::Utf8),
+    }
+}
+
+fn field_infos(fields: &Fields, format: &Format) -> Vec<FieldInfo> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            FieldInfo::new(
+                field.name().clone(),
+                None,
+                None,
+                pg_type(field.data_type()).0,
+                format.format_for(i),
+            )
+        })
+        .collect()
+}
+
+// the types of `$1`, `$2`..., text when they can't be inferred
+fn parameter_types(plan: &LogicalPlan) -> PgWireResult<Vec<DataType>> {
+    let types = plan.get_parameter_types().map_err(user_error)?;
+    Ok((1..=types.len())
+        .map(|i| {
+            types
+                .get(&format!("${}", i))
+                .cloned()
+                .flatten()
+                .unwrap_or(DataType::Utf8)
+        })
+        .collect())
+}
+
+fn parameter(
+    portal: &Portal<LogicalPlan>,
+    i: usize,
+    data_type: &DataType,
+) -> PgWireResult<ScalarValue> {
+    let (ty, encoded) = pg_type(data_type);
+    let value = match encoded {
+        DataType::Boolean => ScalarValue::Boolean(portal.parameter(i, &ty)?),
+        DataType::Int16 => ScalarValue::Int16(portal.parameter(i, &ty)?),
+        DataType::Int32 => ScalarValue::Int32(portal.parameter(i, &ty)?),
+        DataType::Int64 => ScalarValue::Int64(portal.parameter(i, &ty)?),
+        DataType::Float32 => ScalarValue::Float32(portal.parameter(i, &ty)?),
+        DataType::Float64 => ScalarValue::Float64(portal.parameter(i, &ty)?),
+        DataType::Date32 => ScalarValue::Date32(
+            portal
+                .parameter::<NaiveDate>(i, &ty)?
+                .map(Date32Type::from_naive_date),
+        ),
+        DataType::Timestamp(_, None) => ScalarValue::TimestampMicrosecond(
+            portal
+                .parameter::<NaiveDateTime>(i, &ty)?
+                .map(|t| t.and_utc().timestamp_micros()),
+            None,
+        ),
+        DataType::Timestamp(_, tz) => ScalarValue::TimestampMicrosecond(
+            portal
+                .parameter::<DateTime<Utc>>(i, &ty)?
+                .map(|t| t.timestamp_micros()),
+            tz,
+        ),
+        _ => ScalarValue::Utf8(portal.parameter(i, &ty)?),
+    };
+    value.cast_to(data_type).map_err(user_error)
+}
+
+fn encode_rows(fields: &Arc<Vec<FieldInfo>>, batch: &RecordBatch) -> PgWireResult<Vec<DataRow>> {
+    let columns = batch
+        .columns()
+        .iter()
+        .map(encodable)
+        .collect::<PgWireResult<Vec<_>>>()?;
+    let mut rows = vec![];
+    for row in 0..batch.num_rows() {
+        let mut encoder = DataRowEncoder::new(fields.clone());
+        for column in columns.iter() {
+            encode_value(&mut encoder, column, row)?;
+        }
+        rows.push(encoder.finish()?);
+    }
+    Ok(rows)
+}
+
+// cast a column to the arrow type its Postgres type is encoded from
+fn encodable(array: &ArrayRef) -> PgWireResult<ArrayRef> {
+    let (ty, data_type) = pg_type(array.data_type());
+    if ty != Type::TEXT {
+        return cast(array, &data_type).map_err(user_error);
+    }
+    let formatter =
+        ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default()).map_err(user_error)?;
+    let values =
+        (0..array.len()).map(|i| array.is_valid(i).then(|| formatter.value(i).to_string()));
+    Ok(Arc::new(StringArray::from_iter(values)))
+}
+
+fn encode_value(encoder: &mut DataRowEncoder, array: &ArrayRef, row: usize) -> PgWireResult<()> {
+    let valid = array.is_valid(row);
+    match array.data_type() {
+        DataType::Boolean => encoder.encode_field(&valid.then(|| array.as_boolean().value(row))),
+        DataType::Int16 => {
+            encoder.encode_field(&valid.then(|| array.as_primitive::<Int16Type>().value(row)))
+        }
+        DataType::Int32 => {
+            encoder.encode_field(&valid.then(|| array.as_primitive::<Int32Type>().value(row)))
+        }
+        DataType::Int64 => {
+            encoder.encode_field(&valid.then(|| array.as_primitive::<Int64Type>().value(row)))
+        }
+        DataType::Float32 => {
+            encoder.encode_field(&valid.then(|| array.as_primitive::<Float32Type>().value(row)))
+        }
+        DataType::Float64 => {
+            encoder.encode_field(&valid.then(|| array.as_primitive::<Float64Type>().value(row)))
+        }
+        DataType::Date32 => encoder.encode_field(
+            &array
+                .as_primitive::<Date32Type>()
+                .value_as_date(row)
+                .filter(|_| valid),
+        ),
+        DataType::Timestamp(_, None) => encoder.encode_field(
+            &array
+                .as_primitive::<TimestampMicrosecondType>()
+                .value_as_datetime(row)
+                .filter(|_| valid),
+        ),
+        DataType::Timestamp(_, Some(_)) => encoder.encode_field(
+            &array
+                .as_primitive::<TimestampMicrosecondType>()
+                .value_as_datetime(row)
+                .filter(|_| valid)
+                .map(|t| t.and_utc()),
+        ),
+        _ => encoder.encode_field(&valid.then(|| array.as_string::<i32>().value(row))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Decimal128Array, Int64Array, Int8Array};
+    use datafusion::datasource::MemTable;
+    use tokio_postgres::{NoTls, SimpleQueryMessage};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_serve() -> anyhow::Result<()> {
+        let ctx = SessionContext::new();
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+            ),
+        ])?;
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+        ctx.register_table("t", Arc::new(table))?;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        tokio::spawn(serve(ctx, listener));
+
+        let conn = format!("host=127.0.0.1 port={} user=taotie", port);
+        let (client, connection) = tokio_postgres::connect(&conn, NoTls).await?;
+        tokio::spawn(connection);
+
+        let names: Vec<_> = client
+            .simple_query("select name from t order by id")
+            .await?
+            .into_iter()
+            .filter_map(|message| match message {
+                SimpleQueryMessage::Row(row) => row.get(0).map(|v| v.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+
+        let rows = client
+            .query("select id, name from t where id > $1 order by id", &[&1i64])
+            .await?;
+        let rows: Vec<(i64, String)> = rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+        assert_eq!(rows, vec![(2, "b".to_string()), (3, "c".to_string())]);
+
+        assert!(client.simple_query("drop table t").await.is_err());
+        assert!(client
+            .simple_query("set extra_float_digits = 3")
+            .await
+            .is_ok());
+        assert!(client.simple_query("begin").await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encodable() {
+        let array: ArrayRef = Arc::new(Int8Array::from(vec![Some(1), None]));
+        let encoded = encodable(&array).unwrap();
+        assert_eq!(encoded.data_type(), &DataType::Int16);
+        assert!(encoded.is_null(1));
+
+        let array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![12345])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        );
+        let encoded = encodable(&array).unwrap();
+        assert_eq!(encoded.as_string::<i32>().value(0), "123.45");
+        assert_eq!(pg_type(array.data_type()).0, Type::TEXT);
+    }
+}
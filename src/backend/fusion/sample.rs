@@ -0,0 +1,110 @@
+use arrow::datatypes::Schema;
+use datafusion::prelude::DataFrame;
+
+use crate::{backend::OutputOptions, utils::quote_ident, ReplDisplay, SampleOpts};
+
+pub(super) const SAMPLE_TABLE: &str = "__taotie_sample";
+
+#[derive(Debug)]
+pub struct Sample {
+    pub rows: DataFrame,
+    // only when it was chosen at random, to sample the same rows again with `--seed`
+    pub seed: Option<u64>,
+}
+
+// Rows are ranked by the md5 of the seed and their values, so the same seed always gives the
+// same sample regardless of how the scan is partitioned. Identical rows share the same hash.
+pub(super) fn sample_sql(schema: &Schema, opts: &SampleOpts, seed: u64) -> anyhow::Result<String> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|f| quote_ident(f.name()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let values = schema
+        .fields()
+        .iter()
+        .filter(|f| !f.data_type().is_nested())
+        .map(|f| {
+            format!(
+                "coalesce(CAST({} AS VARCHAR), '<null>')",
+                quote_ident(f.name())
+            )
+        })
+        .collect::<Vec<_>>();
+    let hashed = format!(
+        "SELECT {}, md5(concat_ws('|', '{}', {})) AS __taotie_hash FROM {}",
+        columns,
+        seed,
+        values.join(", "),
+        SAMPLE_TABLE
+    );
+
+    let sql = match (opts.fraction, opts.n.unwrap_or(100), &opts.stratify_by) {
+        (Some(fraction), _, _) => {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                anyhow::bail!("fraction must be in (0, 1]");
+            }
+            if fraction == 1.0 {
+                format!("SELECT {} FROM {}", columns, SAMPLE_TABLE)
+            } else {
+                // compare the first 32 bits of the hash, hex strings of the same length sort numerically
+                let threshold = (fraction * u32::MAX as f64) as u32;
+                format!(
+                    "SELECT {} FROM ({}) AS s WHERE substr(__taotie_hash, 1, 8) <= '{:08x}'",
+                    columns, hashed, threshold
+                )
+            }
+        }
+        (None, n, None) => format!(
+            "SELECT {} FROM ({}) AS s ORDER BY __taotie_hash LIMIT {}",
+            columns, hashed, n
+        ),
+        (None, n, Some(stratify_by)) => format!(
+            "SELECT {} FROM (SELECT *, row_number() OVER (PARTITION BY {} ORDER BY __taotie_hash) AS __taotie_rank FROM ({}) AS s) AS r WHERE __taotie_rank <= {}",
+            columns,
+            quote_ident(stratify_by),
+            hashed,
+            n
+        ),
+    };
+    Ok(sql)
+}
+
+impl ReplDisplay for Sample {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let rows = self.rows.display(output).await?;
+        match self.seed {
+            Some(seed) => Ok(format!("{}\nSampled with seed {}", rows, seed)),
+            None => Ok(rows),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{DataType, Field};
+
+    use super::*;
+
+    #[test]
+    fn test_sample_sql() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("city", DataType::Utf8, true),
+        ]);
+        let opts = SampleOpts::new("t".into(), Some(10), None, Some(7), None, None);
+        let sql = sample_sql(&schema, &opts, 7).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT \"id\", \"city\" FROM (SELECT \"id\", \"city\", md5(concat_ws('|', '7', coalesce(CAST(\"id\" AS VARCHAR), '<null>'), coalesce(CAST(\"city\" AS VARCHAR), '<null>'))) AS __taotie_hash FROM __taotie_sample) AS s ORDER BY __taotie_hash LIMIT 10"
+        );
+
+        let opts = SampleOpts::new("t".into(), None, Some(0.5), None, None, None);
+        let sql = sample_sql(&schema, &opts, 0).unwrap();
+        assert!(sql.ends_with("WHERE substr(__taotie_hash, 1, 8) <= '7fffffff'"));
+
+        let opts = SampleOpts::new("t".into(), None, Some(1.5), None, None, None);
+        assert!(sample_sql(&schema, &opts, 0).is_err());
+    }
+}
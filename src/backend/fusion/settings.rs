@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, RecordBatch, StringArray};
+use datafusion::config::ConfigOptions;
+
+// a setting with its value and description
+pub(super) type Setting = (String, Option<String>, String);
+
+// short names for datafusion options whose last segment is ambiguous or unintuitive
+const ALIASES: [(&str, &str); 1] = [("timezone", "time_zone")];
+
+/// Resolve a datafusion option by its full key, e.g. `datafusion.execution.batch_size`, or by
+/// its last segment, e.g. `batch_size`.
+pub(super) fn config_key(options: &ConfigOptions, name: &str) -> anyhow::Result<String> {
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, key)| key);
+    let entries = options.entries();
+    if entries.iter().any(|e| e.key == name) {
+        return Ok(name.to_string());
+    }
+
+    let suffix = format!(".{}", name);
+    let keys: Vec<_> = entries
+        .into_iter()
+        .filter(|e| e.key.ends_with(&suffix))
+        .map(|e| e.key)
+        .collect();
+    match keys.as_slice() {
+        [key] => Ok(key.clone()),
+        [] => anyhow::bail!("unknown option: {}", name),
+        keys => anyhow::bail!("ambiguous option {}: {}", name, keys.join(", ")),
+    }
+}
+
+pub(super) fn config_settings(options: &ConfigOptions) -> Vec<Setting> {
+    options
+        .entries()
+        .into_iter()
+        .map(|e| (e.key, e.value, e.description.to_string()))
+        .collect()
+}
+
+pub(super) fn settings_batch(settings: Vec<Setting>) -> anyhow::Result<RecordBatch> {
+    let (names, (values, descriptions)): (Vec<_>, (Vec<_>, Vec<_>)) = settings
+        .into_iter()
+        .map(|(name, value, description)| (name, (value, description)))
+        .unzip();
+    Ok(RecordBatch::try_from_iter(vec![
+        ("name", Arc::new(StringArray::from(names)) as ArrayRef),
+        ("value", Arc::new(StringArray::from(values)) as ArrayRef),
+        (
+            "description",
+            Arc::new(StringArray::from(descriptions)) as ArrayRef,
+        ),
+    ])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_key() {
+        let options = ConfigOptions::new();
+        assert_eq!(
+            config_key(&options, "batch_size").unwrap(),
+            "datafusion.execution.batch_size"
+        );
+        assert_eq!(
+            config_key(&options, "timezone").unwrap(),
+            "datafusion.execution.time_zone"
+        );
+        assert_eq!(
+            config_key(&options, "datafusion.execution.target_partitions").unwrap(),
+            "datafusion.execution.target_partitions"
+        );
+        assert!(config_key(&options, "no_such_option").is_err());
+    }
+}
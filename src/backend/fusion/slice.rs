@@ -0,0 +1,135 @@
+use std::{collections::VecDeque, fs::File, ops::Range};
+
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
+use datafusion::prelude::DataFrame;
+use futures::TryStreamExt;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use crate::backend::files::{list_files, parquet_metadata};
+
+/// The total number of rows of a parquet dataset, read from the footers only.
+pub(super) fn parquet_num_rows(location: &str) -> anyhow::Result<usize> {
+    let mut rows = 0;
+    for file in list_files(location)? {
+        rows += parquet_metadata(&file)?.file_metadata().num_rows() as usize;
+    }
+    Ok(rows)
+}
+
+/// Read a range of rows of a parquet dataset in file order, decoding only the row groups that
+/// overlap with the range.
+pub(super) fn read_parquet_rows(
+    location: &str,
+    range: Range<usize>,
+) -> anyhow::Result<(SchemaRef, Vec<RecordBatch>)> {
+    let mut schema = None;
+    let mut batches = vec![];
+    // the index of the first row of the current row group in the whole dataset
+    let mut start = 0;
+    for file in list_files(location)? {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&file)?)?;
+        schema.get_or_insert_with(|| builder.schema().clone());
+
+        let mut row_groups = vec![];
+        let mut offset = None;
+        for (i, rg) in builder.metadata().row_groups().iter().enumerate() {
+            let end = start + rg.num_rows() as usize;
+            if start < range.end && end > range.start {
+                row_groups.push(i);
+                offset.get_or_insert(range.start.saturating_sub(start));
+            }
+            start = end;
+        }
+        let Some(offset) = offset else {
+            if start >= range.end {
+                break;
+            }
+            continue;
+        };
+
+        let taken = batches
+            .iter()
+            .map(|b: &RecordBatch| b.num_rows())
+            .sum::<usize>();
+        let reader = builder
+            .with_row_groups(row_groups)
+            .with_offset(offset)
+            .with_limit(range.len() - taken)
+            .build()?;
+        for batch in reader {
+            batches.push(batch?);
+        }
+        if start >= range.end {
+            break;
+        }
+    }
+
+    let schema = schema.ok_or_else(|| anyhow::anyhow!("no parquet files found in {}", location))?;
+    Ok((schema, batches))
+}
+
+/// The last `size` rows of a dataframe in a single pass, holding only the batches that may
+/// contain them, instead of counting the rows first and scanning again to the offset.
+pub(super) async fn tail_rows(
+    df: DataFrame,
+    size: usize,
+) -> anyhow::Result<(SchemaRef, Vec<RecordBatch>)> {
+    let mut stream = df.execute_stream().await?;
+    let schema = stream.schema();
+    let mut batches = VecDeque::new();
+    let mut rows = 0;
+    while let Some(batch) = stream.try_next().await? {
+        rows += batch.num_rows();
+        batches.push_back(batch);
+        while let Some(first) = batches.front() {
+            if rows - first.num_rows() < size {
+                break;
+            }
+            rows -= first.num_rows();
+            batches.pop_front();
+        }
+    }
+    // the first batch may start before the last `size` rows
+    if let Some(first) = batches.front_mut() {
+        let skip = rows.saturating_sub(size);
+        *first = first.slice(skip, first.num_rows() - skip);
+    }
+    Ok((schema, batches.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, AsArray, Int64Array},
+        datatypes::Int64Type,
+    };
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tail_rows_across_batches() -> anyhow::Result<()> {
+        let batches = [vec![1, 2, 3], vec![4, 5], vec![6, 7, 8]]
+            .into_iter()
+            .map(|ids| {
+                RecordBatch::try_from_iter(vec![(
+                    "id",
+                    Arc::new(Int64Array::from(ids)) as ArrayRef,
+                )])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let df = SessionContext::new().read_batches(batches)?;
+
+        for (size, expected) in [(0, vec![]), (4, vec![5, 6, 7, 8]), (9, (1..=8).collect())] {
+            let (_, batches) = tail_rows(df.clone(), size).await?;
+            let ids: Vec<i64> = batches
+                .iter()
+                .flat_map(|b| b.column(0).as_primitive::<Int64Type>().values().to_vec())
+                .collect();
+            assert_eq!(ids, expected);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use std::{collections::BTreeSet, path::Path, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray},
+    compute::concat_batches,
+};
+use parquet::file::{
+    metadata::{ColumnChunkMetaData, ParquetMetaData},
+    statistics::Statistics,
+};
+
+use super::{
+    files::{codec_name, list_files, parquet_metadata},
+    format_batches, OutputOptions,
+};
+use crate::ReplDisplay;
+
+const MAX_VALUE_LEN: usize = 48;
+
+#[derive(Debug)]
+pub struct ParquetInspection {
+    files: RecordBatch,
+    metadata: RecordBatch,
+    row_groups: RecordBatch,
+    columns: RecordBatch,
+}
+
+impl ParquetInspection {
+    pub fn try_new(location: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let files = list_files(location)?;
+        if files.is_empty() {
+            anyhow::bail!("no parquet file found");
+        }
+
+        let mut sections: [Vec<RecordBatch>; 4] = Default::default();
+        for file in files.iter() {
+            let name = file.display().to_string();
+            let metadata = parquet_metadata(file)?;
+            sections[0].push(file_batch(&name, &metadata)?);
+            sections[1].push(key_value_batch(&name, &metadata)?);
+            sections[2].push(row_group_batch(&name, &metadata)?);
+            sections[3].push(column_batch(&name, &metadata)?);
+        }
+        let [files, metadata, row_groups, columns] =
+            sections.map(|batches| concat_batches(&batches[0].schema(), &batches));
+
+        Ok(Self {
+            files: files?,
+            metadata: metadata?,
+            row_groups: row_groups?,
+            columns: columns?,
+        })
+    }
+}
+
+impl ReplDisplay for ParquetInspection {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let tables = [
+            ("Files", self.files),
+            ("Key-value metadata", self.metadata),
+            ("Row groups", self.row_groups),
+            ("Column chunks", self.columns),
+        ];
+        let mut sections = vec![];
+        for (title, batch) in tables {
+            sections.push(format!(
+                "{}:\n{}",
+                title,
+                format_batches(&[batch], &output)?
+            ));
+        }
+        Ok(sections.join("\n\n"))
+    }
+}
+
+fn file_batch(name: &str, metadata: &ParquetMetaData) -> anyhow::Result<RecordBatch> {
+    let fm = metadata.file_metadata();
+    to_batch(vec![
+        ("file", strings([name])),
+        ("version", ints([fm.version() as i64])),
+        ("created_by", opt_strings([fm.created_by()])),
+        ("rows", ints([fm.num_rows()])),
+        ("row_groups", ints([metadata.num_row_groups() as i64])),
+    ])
+}
+
+fn key_value_batch(name: &str, metadata: &ParquetMetaData) -> anyhow::Result<RecordBatch> {
+    let kvs = metadata
+        .file_metadata()
+        .key_value_metadata()
+        .cloned()
+        .unwrap_or_default();
+    to_batch(vec![
+        ("file", strings(kvs.iter().map(|_| name))),
+        ("key", strings(kvs.iter().map(|kv| &kv.key))),
+        (
+            "value",
+            opt_strings(kvs.iter().map(|kv| kv.value.as_deref().map(truncate))),
+        ),
+    ])
+}
+
+fn row_group_batch(name: &str, metadata: &ParquetMetaData) -> anyhow::Result<RecordBatch> {
+    let rgs = metadata.row_groups();
+    to_batch(vec![
+        ("file", strings(rgs.iter().map(|_| name))),
+        ("row_group", ints(0..rgs.len() as i64)),
+        ("rows", ints(rgs.iter().map(|rg| rg.num_rows()))),
+        ("bytes", ints(rgs.iter().map(|rg| rg.total_byte_size()))),
+        (
+            "compressed_bytes",
+            ints(rgs.iter().map(|rg| rg.compressed_size())),
+        ),
+    ])
+}
+
+fn column_batch(name: &str, metadata: &ParquetMetaData) -> anyhow::Result<RecordBatch> {
+    let chunks: Vec<(i64, &ColumnChunkMetaData)> = metadata
+        .row_groups()
+        .iter()
+        .enumerate()
+        .flat_map(|(i, rg)| rg.columns().iter().map(move |c| (i as i64, c)))
+        .collect();
+    let stats = |f: fn(&Statistics) -> Option<String>| {
+        opt_strings(chunks.iter().map(move |(_, c)| c.statistics().and_then(f)))
+    };
+
+    to_batch(vec![
+        ("file", strings(chunks.iter().map(|_| name))),
+        ("row_group", ints(chunks.iter().map(|(i, _)| *i))),
+        (
+            "column",
+            strings(chunks.iter().map(|(_, c)| c.column_path().string())),
+        ),
+        (
+            "type",
+            strings(chunks.iter().map(|(_, c)| c.column_type().to_string())),
+        ),
+        (
+            "codec",
+            strings(chunks.iter().map(|(_, c)| codec_name(c.compression()))),
+        ),
+        (
+            "encodings",
+            strings(chunks.iter().map(|(_, c)| encodings(c))),
+        ),
+        (
+            "compressed_bytes",
+            ints(chunks.iter().map(|(_, c)| c.compressed_size())),
+        ),
+        (
+            "bytes",
+            ints(chunks.iter().map(|(_, c)| c.uncompressed_size())),
+        ),
+        ("min", stats(|s| stat_value(s, true))),
+        ("max", stats(|s| stat_value(s, false))),
+        (
+            "nulls",
+            Arc::new(Int64Array::from_iter(
+                chunks
+                    .iter()
+                    .map(|(_, c)| c.statistics().map(|s| s.null_count() as i64)),
+            )) as ArrayRef,
+        ),
+        (
+            "dictionary",
+            bools(
+                chunks
+                    .iter()
+                    .map(|(_, c)| c.dictionary_page_offset().is_some()),
+            ),
+        ),
+        (
+            "bloom_filter",
+            bools(
+                chunks
+                    .iter()
+                    .map(|(_, c)| c.bloom_filter_offset().is_some()),
+            ),
+        ),
+    ])
+}
+
+fn encodings(column: &ColumnChunkMetaData) -> String {
+    let encodings: BTreeSet<_> = column
+        .encodings()
+        .iter()
+        .map(|e| format!("{:?}", e))
+        .collect();
+    encodings.into_iter().collect::<Vec<_>>().join(",")
+}
+
+fn stat_value(stats: &Statistics, min: bool) -> Option<String> {
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    let bytes = if min {
+        stats.min_bytes()
+    } else {
+        stats.max_bytes()
+    };
+    let value = match stats {
+        Statistics::Boolean(_) => (bytes.first() == Some(&1)).to_string(),
+        Statistics::Int32(_) => i32::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        Statistics::Int64(_) => i64::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        Statistics::Float(_) => f32::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        Statistics::Double(_) => f64::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        Statistics::ByteArray(_) => String::from_utf8_lossy(bytes).to_string(),
+        Statistics::Int96(_) | Statistics::FixedLenByteArray(_) => format!("{:02x?}", bytes),
+    };
+    Some(truncate(&value))
+}
+
+// all fields are nullable so that batches of different files share the same schema
+fn to_batch(columns: Vec<(&str, ArrayRef)>) -> anyhow::Result<RecordBatch> {
+    let batch = RecordBatch::try_from_iter_with_nullable(
+        columns.into_iter().map(|(name, array)| (name, array, true)),
+    )?;
+    Ok(batch)
+}
+
+fn truncate(value: &str) -> String {
+    if value.chars().count() <= MAX_VALUE_LEN {
+        return value.to_string();
+    }
+    let prefix: String = value.chars().take(MAX_VALUE_LEN).collect();
+    format!("{}...", prefix)
+}
+
+fn strings<S: AsRef<str>>(values: impl IntoIterator<Item = S>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn opt_strings<S: AsRef<str>>(values: impl IntoIterator<Item = Option<S>>) -> ArrayRef {
+    Arc::new(values.into_iter().collect::<StringArray>())
+}
+
+fn ints(values: impl IntoIterator<Item = i64>) -> ArrayRef {
+    Arc::new(Int64Array::from_iter_values(values))
+}
+
+fn bools(values: impl IntoIterator<Item = bool>) -> ArrayRef {
+    Arc::new(values.into_iter().map(Some).collect::<BooleanArray>())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{array::AsArray, datatypes::Int64Type};
+
+    use super::*;
+    use crate::test_utils::TestDir;
+
+    #[test]
+    fn test_inspect() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", ints([3, 1, 2])),
+            (
+                "name",
+                opt_strings([Some("b"), None, Some("a")]) as ArrayRef,
+            ),
+        ])?;
+        let path = dir.write_parquet("t.parquet", &batch, 2)?;
+
+        let inspection = ParquetInspection::try_new(&path)?;
+        let rows = |batch: &RecordBatch, i| {
+            batch
+                .column(i)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec()
+        };
+        assert_eq!(rows(&inspection.files, 3), [3]);
+        assert_eq!(rows(&inspection.files, 4), [2]);
+        assert_eq!(rows(&inspection.row_groups, 1), [0, 1]);
+        assert_eq!(rows(&inspection.row_groups, 2), [2, 1]);
+
+        // one chunk per column and row group, with the min, max and nulls of each
+        let columns = &inspection.columns;
+        let strs = |name| {
+            columns
+                .column_by_name(name)
+                .unwrap()
+                .as_string::<i32>()
+                .iter()
+                .map(|v| v.map(|v| v.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let s = |v: &str| Some(v.to_string());
+        assert_eq!(strs("column"), [s("id"), s("name"), s("id"), s("name")]);
+        assert_eq!(strs("min"), [s("1"), s("b"), s("2"), s("a")]);
+        assert_eq!(strs("max"), [s("3"), s("b"), s("2"), s("a")]);
+        let nulls = columns
+            .column_by_name("nulls")
+            .unwrap()
+            .as_primitive::<Int64Type>();
+        assert_eq!(nulls.values().to_vec(), [0, 1, 0, 0]);
+        Ok(())
+    }
+}
@@ -1,3 +1,22 @@
+mod catalog;
+mod check;
+mod files;
 mod fusion;
+mod inspect;
+mod output;
+mod pl;
+mod schema_diff;
+mod stats;
+mod verify;
 
+pub use catalog::{DatasetInfo, DatasetSource};
+pub use check::{CheckReport, Rule, RuleResult, Value};
 pub use fusion::DataFusionBackend;
+pub use inspect::ParquetInspection;
+pub use output::{OutputFormat, OutputOptions};
+pub use pl::PolarsBackend;
+pub use schema_diff::{SchemaChange, SchemaDiff, TypeChange};
+pub use stats::CommandStats;
+pub use verify::{BackendResult, VerifyReport};
+
+pub(crate) use output::format_batches;
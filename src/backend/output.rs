@@ -0,0 +1,153 @@
+use std::{fmt, str::FromStr};
+
+use arrow::{
+    array::RecordBatch, csv, json::LineDelimitedWriter, util::pretty::pretty_format_batches,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+/// How query results are rendered, set via `set format` and `set max_rows`. Each backend has
+/// its own, which its snapshots copy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    // `None` shows all rows
+    pub max_rows: Option<usize>,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Table,
+            max_rows: None,
+        }
+    }
+}
+
+impl OutputOptions {
+    /// Set `format` or `max_rows`, returning `false` for any other option.
+    pub fn set(&mut self, name: &str, value: &str) -> anyhow::Result<bool> {
+        match name {
+            "format" => self.format = value.parse()?,
+            "max_rows" => {
+                self.max_rows = match value {
+                    "none" | "0" => None,
+                    v => Some(v.parse()?),
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// Render query results with the output options of the backend.
+pub(crate) fn format_batches(
+    batches: &[RecordBatch],
+    opts: &OutputOptions,
+) -> anyhow::Result<String> {
+    let batches = truncate(batches, opts.max_rows);
+    match opts.format {
+        OutputFormat::Table => Ok(pretty_format_batches(&batches)?.to_string()),
+        OutputFormat::Csv => {
+            let mut buf = vec![];
+            let mut writer = csv::Writer::new(&mut buf);
+            for batch in batches.iter() {
+                writer.write(batch)?;
+            }
+            drop(writer);
+            Ok(String::from_utf8(buf)?)
+        }
+        OutputFormat::Json => {
+            let mut writer = LineDelimitedWriter::new(vec![]);
+            writer.write_batches(&batches.iter().collect::<Vec<_>>())?;
+            writer.finish()?;
+            Ok(String::from_utf8(writer.into_inner())?)
+        }
+    }
+}
+
+fn truncate(batches: &[RecordBatch], max_rows: Option<usize>) -> Vec<RecordBatch> {
+    let Some(mut remaining) = max_rows else {
+        return batches.to_vec();
+    };
+    let mut truncated = vec![];
+    for batch in batches {
+        if remaining == 0 {
+            break;
+        }
+        let len = batch.num_rows().min(remaining);
+        truncated.push(batch.slice(0, len));
+        remaining -= len;
+    }
+    truncated
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            v => anyhow::bail!(
+                "unsupported output format: {}, expect table, csv or json",
+                v
+            ),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputFormat::Table => write!(f, "table"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int32Array};
+
+    use super::*;
+
+    #[test]
+    fn test_format_batches() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])
+        .unwrap();
+        let batches = vec![batch.clone(), batch];
+
+        let opts = OutputOptions {
+            format: OutputFormat::Csv,
+            max_rows: Some(4),
+        };
+        assert_eq!(format_batches(&batches, &opts).unwrap(), "a\n1\n2\n3\n1\n");
+
+        let opts = OutputOptions {
+            format: OutputFormat::Json,
+            max_rows: Some(1),
+        };
+        assert_eq!(format_batches(&batches, &opts).unwrap(), "{\"a\":1}\n");
+
+        let mut opts = OutputOptions::default();
+        assert!(opts.set("max_rows", "10").unwrap());
+        assert!(opts.set("format", "csv").unwrap());
+        assert!(!opts.set("timing", "on").unwrap());
+        assert_eq!(opts.max_rows, Some(10));
+        assert_eq!(opts.format, OutputFormat::Csv);
+    }
+}
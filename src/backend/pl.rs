@@ -0,0 +1,466 @@
+use std::{collections::BTreeMap, io::Cursor, ops::Range, sync::Arc};
+
+use anyhow::bail;
+use arrow::{
+    array::RecordBatch,
+    compute::concat_batches,
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    ipc::reader::FileReader,
+};
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use polars::{
+    prelude::{
+        col, len, CsvWriter, DataFrame, DataType as PlDataType, Expr, IntoLazy, IpcWriter,
+        JsonFormat, JsonWriter, LazyCsvReader, LazyFileListReader, LazyFrame, LazyJsonLineReader,
+        ScanArgsParquet, SerWriter, SortMultipleOptions, TimeUnit as PlTimeUnit,
+    },
+    sql::SQLContext,
+};
+
+use crate::{
+    cli::FileOpts,
+    utils::{quote_ident, sort_key, track_setting},
+    Backend, ConnectOpts, ConvertOpts, CorrOpts, CountsOpts, DatasetConn, DiffOpts, DupsOpts,
+    ReplDisplay, SampleOpts, ServeOpts,
+};
+
+use super::{
+    CheckReport, CommandStats, DatasetInfo, DatasetSource, OutputFormat, OutputOptions,
+    ParquetInspection, Rule,
+};
+
+/// A backend on the polars lazy engine, which supports the basic commands only.
+pub struct PolarsBackend {
+    ctx: SQLContext,
+    datasets: BTreeMap<String, DatasetSource>,
+    output: OutputOptions,
+    // the settings changed with `set`, including those it ignores, for another backend
+    changed: Vec<(String, String)>,
+}
+
+impl PolarsBackend {
+    pub fn new() -> Self {
+        Self {
+            ctx: SQLContext::new(),
+            datasets: BTreeMap::new(),
+            output: OutputOptions::default(),
+            changed: vec![],
+        }
+    }
+
+    // running a query registers its CTEs, so run it on a copy of the context
+    fn query(&self, sql: &str) -> anyhow::Result<LazyFrame> {
+        Ok(self.ctx.clone().execute(sql)?)
+    }
+
+    fn ensure_new(&self, name: &str, replace: bool) -> anyhow::Result<()> {
+        if !replace && self.datasets.contains_key(name) {
+            bail!(
+                "dataset already exists: {}, use --replace to replace it",
+                name
+            );
+        }
+        Ok(())
+    }
+
+    fn table(&self, name: &str) -> anyhow::Result<LazyFrame> {
+        if !self.datasets.contains_key(name) {
+            bail!("dataset not found: {}", name);
+        }
+        self.query(&format!("SELECT * FROM {}", quote_ident(name)))
+    }
+
+    fn count_rows(&self, name: &str) -> anyhow::Result<Option<u64>> {
+        let counts = self.table(name)?.select([len()]).collect()?;
+        Ok(counts[0].u32()?.get(0).map(|v| v as u64))
+    }
+
+    fn sorted(&self, name: &str, order_by: &[String]) -> anyhow::Result<LazyFrame> {
+        let lf = self.table(name)?;
+        if order_by.is_empty() {
+            return Ok(lf);
+        }
+        let (exprs, descending): (Vec<Expr>, Vec<bool>) = order_by
+            .iter()
+            .map(|v| {
+                let (name, descending) = sort_key(v);
+                (col(name), descending)
+            })
+            .unzip();
+        let options = SortMultipleOptions::default().with_order_descending_multi(descending);
+        Ok(lf.sort_by_exprs(exprs, options))
+    }
+}
+
+impl Backend for PolarsBackend {
+    type DataFrame = DataFrame;
+
+    async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()> {
+        let lf = match &opts.conn {
+            DatasetConn::Csv(file_opts) => LazyCsvReader::new(uncompressed(file_opts)?)
+                .with_has_header(true)
+                .finish()?,
+            DatasetConn::Parquet(filename) => {
+                LazyFrame::scan_parquet(filename, ScanArgsParquet::default())?
+            }
+            DatasetConn::NdJson(file_opts) => {
+                LazyJsonLineReader::new(uncompressed(file_opts)?).finish()?
+            }
+            DatasetConn::Postgres(_) => bail!("Postgres connection is not supported yet"),
+        };
+        self.ctx.register(&opts.name, lf);
+        self.datasets
+            .insert(opts.name.clone(), DatasetSource::File(opts.conn.clone()));
+        Ok(())
+    }
+
+    async fn list(&self, exact: bool) -> anyhow::Result<impl ReplDisplay> {
+        let mut infos = vec![];
+        for (name, source) in self.datasets.iter() {
+            let mut info = DatasetInfo::new(name, source.kind());
+            info.source = Some(source.source().to_string());
+            if let DatasetSource::File(conn) = source {
+                info.format = Some(conn.format().to_string());
+                info.compression = conn.compression().map(|c| c.to_string());
+            }
+            if let DatasetSource::Memory { rows, size, .. } = source {
+                info.rows = Some(*rows as u64);
+                info.memory = Some(*size);
+            }
+            if info.rows.is_none() && exact {
+                match self.count_rows(name) {
+                    Ok(rows) => info.rows = rows,
+                    Err(e) => info.error = Some(e.to_string()),
+                }
+            }
+            infos.push(info);
+        }
+        DatasetInfo::to_record_batch(&infos)
+    }
+
+    async fn schema(&self, name: &str) -> anyhow::Result<SchemaRef> {
+        let schema = self.table(name)?.schema()?;
+        let fields: Vec<_> = schema
+            .iter()
+            .map(|(name, dtype)| Field::new(name.as_str(), arrow_type(dtype), true))
+            .collect();
+        Ok(Arc::new(Schema::new(fields)))
+    }
+
+    async fn describe(&self, _name: &str, _sparkline: bool) -> anyhow::Result<impl ReplDisplay> {
+        unsupported::<String>("describe")
+    }
+
+    async fn head(&self, name: &str, size: usize) -> anyhow::Result<impl ReplDisplay> {
+        Ok(self.table(name)?.limit(size as u32).collect()?)
+    }
+
+    async fn tail(
+        &self,
+        name: &str,
+        size: usize,
+        order_by: &[String],
+    ) -> anyhow::Result<impl ReplDisplay> {
+        Ok(self.sorted(name, order_by)?.tail(size as u32).collect()?)
+    }
+
+    async fn rows(
+        &self,
+        name: &str,
+        range: Range<usize>,
+        order_by: &[String],
+    ) -> anyhow::Result<impl ReplDisplay> {
+        let lf = self.sorted(name, order_by)?;
+        Ok(lf.slice(range.start as i64, range.len() as u32).collect()?)
+    }
+
+    async fn sql(&self, sql: &str) -> anyhow::Result<impl ReplDisplay> {
+        Ok(self.query(sql)?.collect()?)
+    }
+
+    // through IPC, as polars has its own arrow implementation
+    async fn collect(&self, sql: &str) -> anyhow::Result<RecordBatch> {
+        let mut df = self.query(sql)?.collect()?;
+        let mut buf = vec![];
+        IpcWriter::new(&mut buf)
+            .with_pl_flavor(false)
+            .finish(&mut df)?;
+        let reader = FileReader::try_new(Cursor::new(buf), None)?;
+        let schema = reader.schema();
+        let batches = reader.collect::<Result<Vec<_>, _>>()?;
+        Ok(concat_batches(&schema, &batches)?)
+    }
+
+    async fn explain(&self, sql: &str, analyze: bool) -> anyhow::Result<impl ReplDisplay> {
+        if analyze {
+            bail!("explain --analyze is not supported by the polars backend");
+        }
+        Ok(self.query(sql)?.describe_optimized_plan()?)
+    }
+
+    async fn create_view(&mut self, name: &str, sql: &str, replace: bool) -> anyhow::Result<()> {
+        self.ensure_new(name, replace)?;
+        let lf = self.query(sql)?;
+        self.ctx.register(name, lf);
+        self.datasets
+            .insert(name.to_string(), DatasetSource::View(sql.to_string()));
+        Ok(())
+    }
+
+    async fn cache(&mut self, name: &str, sql: &str, replace: bool) -> anyhow::Result<usize> {
+        self.ensure_new(name, replace)?;
+        let df = self.query(sql)?.collect()?;
+        let (rows, size) = (df.height(), df.estimated_size());
+        self.ctx.register(name, df.lazy());
+        let source = DatasetSource::Memory {
+            sql: sql.to_string(),
+            rows,
+            size,
+        };
+        self.datasets.insert(name.to_string(), source);
+        Ok(size)
+    }
+
+    async fn deregister(&mut self, name: &str) -> anyhow::Result<()> {
+        if self.datasets.remove(name).is_none() {
+            bail!("dataset not found: {}", name);
+        }
+        self.ctx.unregister(name);
+        Ok(())
+    }
+
+    async fn inspect(&self, target: &str) -> anyhow::Result<impl ReplDisplay> {
+        let location = match self.datasets.get(target) {
+            Some(DatasetSource::File(DatasetConn::Parquet(filename))) => filename.as_str(),
+            Some(source) => bail!(
+                "{} is a {} dataset, only parquet files can be inspected",
+                target,
+                source.kind()
+            ),
+            None => target,
+        };
+        ParquetInspection::try_new(location)
+    }
+
+    async fn convert(&self, _opts: &ConvertOpts) -> anyhow::Result<u64> {
+        unsupported("convert")
+    }
+
+    async fn diff(&mut self, _opts: &DiffOpts) -> anyhow::Result<impl ReplDisplay> {
+        unsupported::<String>("diff")
+    }
+
+    async fn dups(&mut self, _opts: &DupsOpts) -> anyhow::Result<impl ReplDisplay> {
+        unsupported::<String>("dups")
+    }
+
+    async fn counts(&self, _opts: &CountsOpts) -> anyhow::Result<impl ReplDisplay> {
+        unsupported::<String>("counts")
+    }
+
+    async fn hist(
+        &self,
+        _name: &str,
+        _column: &str,
+        _bins: usize,
+    ) -> anyhow::Result<impl ReplDisplay> {
+        unsupported::<String>("hist")
+    }
+
+    async fn corr(&self, _opts: &CorrOpts) -> anyhow::Result<impl ReplDisplay> {
+        unsupported::<String>("corr")
+    }
+
+    async fn check(&self, _name: &str, _rules: &[Rule]) -> anyhow::Result<CheckReport> {
+        unsupported("check")
+    }
+
+    async fn sample(&mut self, _opts: &SampleOpts) -> anyhow::Result<impl ReplDisplay> {
+        unsupported::<String>("sample")
+    }
+
+    // only the output options, the engine has no session options, so the others are kept for
+    // another backend, e.g. `memory_limit` from the rc file
+    // only the output options apply, the others are kept for the next backend
+    async fn set(&mut self, name: &str, value: &str) -> anyhow::Result<Option<String>> {
+        let applied = self.output.set(name, value)?;
+        track_setting(&mut self.changed, name, value);
+        Ok((!applied).then(|| format!("the polars backend ignores {}", name)))
+    }
+
+    fn output(&self) -> OutputOptions {
+        self.output
+    }
+
+    fn changed_settings(&self) -> Vec<(String, String)> {
+        self.changed.clone()
+    }
+
+    async fn show(&self, name: &str) -> anyhow::Result<impl ReplDisplay> {
+        let output = self.output;
+        let max_rows = output
+            .max_rows
+            .map_or("none".to_string(), |v| v.to_string());
+        let format = format!("format = {}", output.format);
+        let max_rows = format!("max_rows = {}", max_rows);
+        match name {
+            "all" => Ok(format!("{}\n{}", format, max_rows)),
+            "format" => Ok(format),
+            "max_rows" => Ok(max_rows),
+            name => bail!("unsupported option for the polars backend: {}", name),
+        }
+    }
+
+    fn reset_stats(&mut self) {}
+
+    fn stats(&self) -> Option<CommandStats> {
+        None
+    }
+
+    async fn serve(&self, _opts: &ServeOpts) -> anyhow::Result<()> {
+        unsupported("serve")
+    }
+
+    async fn snapshot(&self) -> anyhow::Result<Self> {
+        Ok(Self {
+            ctx: self.ctx.clone(),
+            datasets: self.datasets.clone(),
+            output: self.output,
+            changed: self.changed.clone(),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "polars"
+    }
+
+    fn catalog(&self) -> Vec<(String, DatasetSource)> {
+        self.datasets
+            .iter()
+            .map(|(name, source)| (name.clone(), source.clone()))
+            .collect()
+    }
+}
+
+impl Default for PolarsBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReplDisplay for DataFrame {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let mut df = match output.max_rows {
+            Some(max_rows) => self.head(Some(max_rows)),
+            None => self,
+        };
+        let mut buf = vec![];
+        match output.format {
+            OutputFormat::Table => return Ok(df.to_string()),
+            OutputFormat::Csv => CsvWriter::new(&mut buf).finish(&mut df)?,
+            OutputFormat::Json => JsonWriter::new(&mut buf)
+                .with_json_format(JsonFormat::JsonLines)
+                .finish(&mut df)?,
+        }
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+fn unsupported<T>(command: &str) -> anyhow::Result<T> {
+    bail!("{} is not supported by the polars backend", command)
+}
+
+// polars can't scan compressed files lazily
+fn uncompressed(file_opts: &FileOpts) -> anyhow::Result<&str> {
+    if file_opts.compression != FileCompressionType::UNCOMPRESSED {
+        bail!("compressed files are not supported by the polars backend");
+    }
+    Ok(&file_opts.filename)
+}
+
+// nested and other types are shown as text
+fn arrow_type(dtype: &PlDataType) -> DataType {
+    let unit = |unit: &PlTimeUnit| match unit {
+        PlTimeUnit::Nanoseconds => TimeUnit::Nanosecond,
+        PlTimeUnit::Microseconds => TimeUnit::Microsecond,
+        PlTimeUnit::Milliseconds => TimeUnit::Millisecond,
+    };
+    match dtype {
+        PlDataType::Boolean => DataType::Boolean,
+        PlDataType::UInt8 => DataType::UInt8,
+        PlDataType::UInt16 => DataType::UInt16,
+        PlDataType::UInt32 => DataType::UInt32,
+        PlDataType::UInt64 => DataType::UInt64,
+        PlDataType::Int8 => DataType::Int8,
+        PlDataType::Int16 => DataType::Int16,
+        PlDataType::Int32 => DataType::Int32,
+        PlDataType::Int64 => DataType::Int64,
+        PlDataType::Float32 => DataType::Float32,
+        PlDataType::Float64 => DataType::Float64,
+        PlDataType::String => DataType::Utf8,
+        PlDataType::Binary => DataType::Binary,
+        PlDataType::Date => DataType::Date32,
+        PlDataType::Time => DataType::Time64(TimeUnit::Nanosecond),
+        PlDataType::Datetime(u, tz) => DataType::Timestamp(unit(u), tz.as_deref().map(Into::into)),
+        PlDataType::Duration(u) => DataType::Duration(unit(u)),
+        PlDataType::List(inner) => {
+            DataType::List(Arc::new(Field::new("item", arrow_type(inner), true)))
+        }
+        PlDataType::Null => DataType::Null,
+        _ => DataType::Utf8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{connect_opts, TestDir};
+
+    #[tokio::test]
+    async fn test_polars_backend() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let csv = dir.write("sales.csv", "region,amount\neast,10\nwest,20\neast,5\n");
+        let mut backend = PolarsBackend::new();
+        backend.connect(&connect_opts("sales", &csv)).await?;
+
+        assert_eq!(backend.set("format", "csv").await?, None);
+        assert_eq!(
+            backend.set("target_partitions", "2").await?,
+            Some("the polars backend ignores target_partitions".to_string())
+        );
+        let output = backend.output();
+
+        let schema = backend.schema("sales").await?;
+        let fields: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone()))
+            .collect();
+        assert_eq!(
+            fields,
+            [("region", DataType::Utf8), ("amount", DataType::Int64)]
+        );
+
+        let sql = "SELECT region, sum(amount) AS total FROM sales GROUP BY region ORDER BY region";
+        let result = backend.sql(sql).await?.display(output).await?;
+        assert_eq!(result, "region,total\neast,15\nwest,20\n");
+
+        let head = backend.head("sales", 2).await?.display(output).await?;
+        assert_eq!(head, "region,amount\neast,10\nwest,20\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrow_type() {
+        let dtype = PlDataType::Datetime(PlTimeUnit::Microseconds, Some("UTC".to_string()));
+        assert_eq!(
+            arrow_type(&dtype),
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+        );
+        let dtype = PlDataType::List(Box::new(PlDataType::Int64));
+        assert_eq!(
+            arrow_type(&dtype),
+            DataType::List(Arc::new(Field::new("item", DataType::Int64, true)))
+        );
+    }
+}
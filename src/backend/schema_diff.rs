@@ -0,0 +1,332 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{ArrayRef, RecordBatch, StringArray},
+    datatypes::{DataType, Field, Fields, Schema, SchemaRef},
+};
+
+use super::{format_batches, OutputOptions};
+use crate::ReplDisplay;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    Added(String, DataType),
+    Removed(String, DataType),
+    // a removed and an added column of the same type, likely a rename
+    Renamed(String, String, DataType),
+    TypeChanged(String, DataType, DataType, TypeChange),
+    NullabilityChanged(String, bool, bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeChange {
+    Widening,
+    Narrowing,
+    Incompatible,
+}
+
+#[derive(Debug)]
+pub struct SchemaDiff(Vec<SchemaChange>);
+
+impl SchemaDiff {
+    pub fn new(a: &Schema, b: &Schema) -> Self {
+        let mut changes = vec![];
+        diff_fields("", a.fields(), b.fields(), &mut changes);
+        Self(changes)
+    }
+
+    pub fn changes(&self) -> &[SchemaChange] {
+        &self.0
+    }
+}
+
+impl ReplDisplay for SchemaRef {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let fields = self.fields();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "column_name",
+                Arc::new(StringArray::from_iter_values(
+                    fields.iter().map(|f| f.name()),
+                )) as ArrayRef,
+            ),
+            (
+                "data_type",
+                Arc::new(StringArray::from_iter_values(
+                    fields.iter().map(|f| f.data_type().to_string()),
+                )) as ArrayRef,
+            ),
+            (
+                "is_nullable",
+                Arc::new(StringArray::from_iter_values(fields.iter().map(|f| {
+                    if f.is_nullable() {
+                        "YES"
+                    } else {
+                        "NO"
+                    }
+                }))) as ArrayRef,
+            ),
+        ])?;
+        format_batches(&[batch], &output)
+    }
+}
+
+impl ReplDisplay for SchemaDiff {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        if self.0.is_empty() {
+            return Ok("Schemas are identical".to_string());
+        }
+
+        let rows: Vec<_> = self.0.iter().map(SchemaChange::to_row).collect();
+        let column = |i: usize| {
+            Arc::new(StringArray::from_iter(rows.iter().map(|r| r[i].clone()))) as ArrayRef
+        };
+        let batch = RecordBatch::try_from_iter(vec![
+            ("column", column(0)),
+            ("change", column(1)),
+            ("from", column(2)),
+            ("to", column(3)),
+            ("note", column(4)),
+        ])?;
+        format_batches(&[batch], &output)
+    }
+}
+
+impl SchemaChange {
+    fn to_row(&self) -> [Option<String>; 5] {
+        match self {
+            SchemaChange::Added(name, dt) => [
+                Some(name.clone()),
+                Some("added".into()),
+                None,
+                Some(dt.to_string()),
+                None,
+            ],
+            SchemaChange::Removed(name, dt) => [
+                Some(name.clone()),
+                Some("removed".into()),
+                Some(dt.to_string()),
+                None,
+                None,
+            ],
+            SchemaChange::Renamed(from, to, dt) => [
+                Some(format!("{} -> {}", from, to)),
+                Some("renamed?".into()),
+                Some(from.clone()),
+                Some(to.clone()),
+                Some(format!("same type {}", dt)),
+            ],
+            SchemaChange::TypeChanged(name, from, to, change) => [
+                Some(name.clone()),
+                Some("type".into()),
+                Some(from.to_string()),
+                Some(to.to_string()),
+                Some(format!("{:?}", change).to_lowercase()),
+            ],
+            SchemaChange::NullabilityChanged(name, from, to) => [
+                Some(name.clone()),
+                Some("nullability".into()),
+                Some(nullability(*from).into()),
+                Some(nullability(*to).into()),
+                None,
+            ],
+        }
+    }
+}
+
+fn nullability(nullable: bool) -> &'static str {
+    if nullable {
+        "nullable"
+    } else {
+        "not null"
+    }
+}
+
+fn diff_fields(prefix: &str, a: &Fields, b: &Fields, changes: &mut Vec<SchemaChange>) {
+    let path = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        }
+    };
+
+    let mut removed = vec![];
+    for (i, fa) in a.iter().enumerate() {
+        match b.find(fa.name()) {
+            Some((_, fb)) => diff_field(&path(fa.name()), fa, fb, changes),
+            None => removed.push((i, fa)),
+        }
+    }
+    let mut added: Vec<_> = b
+        .iter()
+        .enumerate()
+        .filter(|(_, fb)| a.find(fb.name()).is_none())
+        .collect();
+
+    for (i, fa) in removed {
+        let candidate = added
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, fb))| fb.data_type() == fa.data_type())
+            .min_by_key(|(_, (j, _))| i.abs_diff(*j))
+            .map(|(pos, _)| pos);
+        match candidate {
+            Some(pos) => {
+                let (_, fb) = added.remove(pos);
+                changes.push(SchemaChange::Renamed(
+                    path(fa.name()),
+                    path(fb.name()),
+                    fa.data_type().clone(),
+                ));
+            }
+            None => changes.push(SchemaChange::Removed(
+                path(fa.name()),
+                fa.data_type().clone(),
+            )),
+        }
+    }
+    for (_, fb) in added {
+        changes.push(SchemaChange::Added(path(fb.name()), fb.data_type().clone()));
+    }
+}
+
+fn diff_field(path: &str, a: &Field, b: &Field, changes: &mut Vec<SchemaChange>) {
+    if a.is_nullable() != b.is_nullable() {
+        changes.push(SchemaChange::NullabilityChanged(
+            path.to_string(),
+            a.is_nullable(),
+            b.is_nullable(),
+        ));
+    }
+
+    match (a.data_type(), b.data_type()) {
+        (DataType::Struct(fa), DataType::Struct(fb)) => diff_fields(path, fa, fb, changes),
+        (DataType::List(fa), DataType::List(fb))
+        | (DataType::LargeList(fa), DataType::LargeList(fb)) => {
+            diff_field(&format!("{}[]", path), fa, fb, changes)
+        }
+        (ta, tb) if ta != tb => {
+            let change = if is_widening(ta, tb) {
+                TypeChange::Widening
+            } else if is_widening(tb, ta) {
+                TypeChange::Narrowing
+            } else {
+                TypeChange::Incompatible
+            };
+            changes.push(SchemaChange::TypeChanged(
+                path.to_string(),
+                ta.clone(),
+                tb.clone(),
+                change,
+            ));
+        }
+        _ => {}
+    }
+}
+
+// whether every value of `from` can be represented by `to` without loss
+fn is_widening(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    match (from, to) {
+        (Int8, Int16 | Int32 | Int64)
+        | (Int16, Int32 | Int64)
+        | (Int32, Int64)
+        | (UInt8, UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64)
+        | (UInt16, UInt32 | UInt64 | Int32 | Int64)
+        | (UInt32, UInt64 | Int64)
+        | (Float16, Float32 | Float64)
+        | (Float32, Float64)
+        | (Int8 | Int16 | UInt8 | UInt16, Float32 | Float64)
+        | (Int32 | UInt32, Float64)
+        | (Utf8, LargeUtf8)
+        | (Binary, LargeBinary)
+        | (Date32, Date64) => true,
+        (Decimal128(p1, s1), Decimal128(p2, s2) | Decimal256(p2, s2))
+        | (Decimal256(p1, s1), Decimal256(p2, s2)) => {
+            s2 >= s1 && (*p2 as i16 - *s2 as i16) >= (*p1 as i16 - *s1 as i16)
+        }
+        (List(fa), LargeList(fb)) => {
+            fa.data_type() == fb.data_type() || is_widening(fa.data_type(), fb.data_type())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::OutputFormat;
+
+    #[tokio::test]
+    async fn test_schema_output() -> anyhow::Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("email", DataType::Utf8, true),
+        ]));
+        let output = OutputOptions {
+            format: OutputFormat::Csv,
+            max_rows: None,
+        };
+        assert_eq!(
+            schema.display(output).await?,
+            "column_name,data_type,is_nullable\nid,Int32,NO\nemail,Utf8,YES\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let address = Fields::from(vec![
+            Field::new("city", DataType::Utf8, true),
+            Field::new("zip", DataType::Int32, true),
+        ]);
+        let a = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("email", DataType::Utf8, true),
+            Field::new("score", DataType::Float64, true),
+            Field::new("address", DataType::Struct(address), true),
+            Field::new("legacy", DataType::Boolean, true),
+        ]);
+        let address = Fields::from(vec![
+            Field::new("city", DataType::Utf8, false),
+            Field::new("zip", DataType::Utf8, true),
+        ]);
+        let b = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("mail", DataType::Utf8, true),
+            Field::new("score", DataType::Float32, true),
+            Field::new("address", DataType::Struct(address), true),
+            Field::new("created_at", DataType::Date32, true),
+        ]);
+
+        let diff = SchemaDiff::new(&a, &b);
+        assert_eq!(
+            diff.changes(),
+            &[
+                SchemaChange::TypeChanged(
+                    "id".into(),
+                    DataType::Int32,
+                    DataType::Int64,
+                    TypeChange::Widening
+                ),
+                SchemaChange::TypeChanged(
+                    "score".into(),
+                    DataType::Float64,
+                    DataType::Float32,
+                    TypeChange::Narrowing
+                ),
+                SchemaChange::NullabilityChanged("address.city".into(), true, false),
+                SchemaChange::TypeChanged(
+                    "address.zip".into(),
+                    DataType::Int32,
+                    DataType::Utf8,
+                    TypeChange::Incompatible
+                ),
+                SchemaChange::Renamed("email".into(), "mail".into(), DataType::Utf8),
+                SchemaChange::Removed("legacy".into(), DataType::Boolean),
+                SchemaChange::Added("created_at".into(), DataType::Date32),
+            ]
+        );
+    }
+}
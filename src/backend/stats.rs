@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use crate::utils::human_bytes;
+
+/// Resource usage of a command, shown after it runs with `set timing on`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CommandStats {
+    // the rows returned by the queries of the command
+    pub rows: Option<u64>,
+    // only reported by parquet scans
+    pub bytes_scanned: Option<u64>,
+    // the peak of memory reserved by operators like sorts, joins and aggregations
+    pub peak_memory: usize,
+}
+
+impl CommandStats {
+    pub fn summary(&self, elapsed: Duration) -> String {
+        let or_na = |v: Option<String>| v.unwrap_or_else(|| "n/a".to_string());
+        format!(
+            "Time: {:.3?}, rows: {}, scanned: {}, peak memory: {}",
+            elapsed,
+            or_na(self.rows.map(|v| v.to_string())),
+            or_na(self.bytes_scanned.map(human_bytes)),
+            human_bytes(self.peak_memory as u64)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let stats = CommandStats {
+            rows: Some(10),
+            bytes_scanned: None,
+            peak_memory: 2048,
+        };
+        assert_eq!(
+            stats.summary(Duration::from_millis(5)),
+            "Time: 5.000ms, rows: 10, scanned: n/a, peak memory: 2.0 KiB"
+        );
+    }
+}
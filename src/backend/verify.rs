@@ -0,0 +1,353 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray, RecordBatch, StringArray},
+    compute::cast,
+    datatypes::{DataType, Field, Float64Type, TimeUnit},
+    util::display::{ArrayFormatter, FormatOptions},
+};
+
+use super::{format_batches, OutputOptions};
+use crate::ReplDisplay;
+
+// the rows shown of each side when the values differ
+const MAX_ROWS: usize = 5;
+
+#[derive(Debug)]
+pub struct BackendResult {
+    pub backend: String,
+    // `None` when the query failed
+    pub rows: Option<usize>,
+    pub differences: Vec<String>,
+    // the datasets of the catalog that couldn't be registered on the backend, with their error
+    pub replay_errors: Vec<String>,
+}
+
+/// The results of a query on every backend, compared with those of the first.
+#[derive(Debug)]
+pub struct VerifyReport(Vec<BackendResult>);
+
+// the results with normalised types, one string per value, in sorted order
+struct Normalised {
+    fields: Vec<(String, DataType)>,
+    rows: Vec<Vec<String>>,
+}
+
+impl VerifyReport {
+    /// Compare the results of each backend with those of the first, which must succeed, along
+    /// with the errors of registering the datasets on the backend.
+    pub fn try_new(
+        results: Vec<(String, Vec<String>, anyhow::Result<RecordBatch>)>,
+    ) -> anyhow::Result<Self> {
+        let mut results = results.into_iter();
+        let Some((reference, replay_errors, batch)) = results.next() else {
+            anyhow::bail!("no backend to verify with");
+        };
+        let batch =
+            batch.map_err(|e| anyhow::anyhow!("the query failed on {}: {}", reference, e))?;
+        let expected = Normalised::try_new(&batch)?;
+        let mut report = vec![BackendResult {
+            backend: reference.clone(),
+            rows: Some(expected.rows.len()),
+            differences: vec![],
+            replay_errors,
+        }];
+
+        for (backend, replay_errors, batch) in results {
+            let result = match batch.and_then(|batch| Normalised::try_new(&batch)) {
+                Ok(actual) => BackendResult {
+                    rows: Some(actual.rows.len()),
+                    differences: expected.compare(&actual, &reference, &backend),
+                    backend,
+                    replay_errors,
+                },
+                Err(e) => BackendResult {
+                    backend,
+                    rows: None,
+                    differences: vec![format!("query failed: {}", e)],
+                    replay_errors,
+                },
+            };
+            report.push(result);
+        }
+        Ok(Self(report))
+    }
+
+    pub fn results(&self) -> &[BackendResult] {
+        &self.0
+    }
+
+    /// Whether every backend returned the same results.
+    pub fn matches(&self) -> bool {
+        self.0.iter().all(|r| r.differences.is_empty())
+    }
+}
+
+impl Normalised {
+    fn try_new(batch: &RecordBatch) -> anyhow::Result<Self> {
+        let schema = batch.schema();
+        let mut fields = vec![];
+        let mut columns = vec![];
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            let data_type = normalise_type(field.data_type());
+            let column = cast(column, &data_type)?;
+            fields.push((field.name().clone(), data_type));
+            columns.push(format_values(&column)?);
+        }
+
+        let mut rows: Vec<Vec<String>> = (0..batch.num_rows())
+            .map(|i| columns.iter().map(|c| c[i].clone()).collect())
+            .collect();
+        rows.sort();
+        Ok(Self { fields, rows })
+    }
+
+    fn compare(&self, other: &Normalised, this_name: &str, other_name: &str) -> Vec<String> {
+        let mut differences = vec![];
+        if self.fields.len() != other.fields.len() {
+            differences.push(format!(
+                "{} columns on {}, {} on {}",
+                self.fields.len(),
+                this_name,
+                other.fields.len(),
+                other_name
+            ));
+            return differences;
+        }
+        for ((name, data_type), (other_field, other_type)) in self.fields.iter().zip(&other.fields)
+        {
+            if name != other_field || data_type != other_type {
+                differences.push(format!(
+                    "column {} {} on {}, {} {} on {}",
+                    name, data_type, this_name, other_field, other_type, other_name
+                ));
+            }
+        }
+        if self.rows.len() != other.rows.len() {
+            differences.push(format!(
+                "{} rows on {}, {} on {}",
+                self.rows.len(),
+                this_name,
+                other.rows.len(),
+                other_name
+            ));
+        }
+
+        let (missing, extra) = sorted_difference(&self.rows, &other.rows);
+        for (rows, name) in [(missing, this_name), (extra, other_name)] {
+            if rows.is_empty() {
+                continue;
+            }
+            differences.push(format!("{} rows only on {}", rows.len(), name));
+            for row in rows.iter().take(MAX_ROWS) {
+                differences.push(format!("  ({})", row.join(", ")));
+            }
+        }
+        differences
+    }
+}
+
+impl ReplDisplay for VerifyReport {
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String> {
+        let status = |(i, r): (usize, &BackendResult)| match (i, r.rows) {
+            (0, _) => "REFERENCE",
+            (_, None) => "ERROR",
+            _ if r.differences.is_empty() => "MATCH",
+            _ => "DIFF",
+        };
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "backend",
+                Arc::new(StringArray::from_iter_values(
+                    self.0.iter().map(|r| r.backend.as_str()),
+                )) as ArrayRef,
+            ),
+            (
+                "status",
+                Arc::new(StringArray::from_iter_values(
+                    self.0.iter().enumerate().map(status),
+                )) as ArrayRef,
+            ),
+            (
+                "rows",
+                Arc::new(StringArray::from(
+                    self.0
+                        .iter()
+                        .map(|r| r.rows.map(|v| v.to_string()))
+                        .collect::<Vec<_>>(),
+                )) as ArrayRef,
+            ),
+        ])?;
+
+        let mut output = format_batches(&[batch], &output)?;
+        for result in &self.0 {
+            let lines: Vec<_> = result
+                .differences
+                .iter()
+                .cloned()
+                .chain(
+                    result
+                        .replay_errors
+                        .iter()
+                        .map(|e| format!("failed to register {}", e)),
+                )
+                .collect();
+            if !lines.is_empty() {
+                output.push_str(&format!("\n\n{}:\n{}", result.backend, lines.join("\n")));
+            }
+        }
+        Ok(output)
+    }
+}
+
+// the widest type of each family, as engines differ in the widths they infer and return
+fn normalise_type(data_type: &DataType) -> DataType {
+    match data_type {
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => DataType::Int64,
+        DataType::Float16
+        | DataType::Float32
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => DataType::Float64,
+        DataType::LargeUtf8 | DataType::Utf8View => DataType::Utf8,
+        DataType::LargeBinary | DataType::BinaryView => DataType::Binary,
+        DataType::Date64 => DataType::Date32,
+        DataType::Timestamp(_, tz) => DataType::Timestamp(TimeUnit::Microsecond, tz.clone()),
+        DataType::Time32(_) | DataType::Time64(_) => DataType::Time64(TimeUnit::Microsecond),
+        DataType::Duration(_) => DataType::Duration(TimeUnit::Microsecond),
+        DataType::List(field) | DataType::LargeList(field) => {
+            let item = Field::new(field.name(), normalise_type(field.data_type()), true);
+            DataType::List(Arc::new(item))
+        }
+        data_type => data_type.clone(),
+    }
+}
+
+// floats are rounded to 12 significant digits, as the order of aggregation differs
+fn format_values(column: &ArrayRef) -> anyhow::Result<Vec<String>> {
+    if let Some(floats) = column.as_primitive_opt::<Float64Type>() {
+        return Ok(floats
+            .iter()
+            .map(|v| match v {
+                Some(v) if v.is_finite() => {
+                    let rounded: f64 = format!("{:.11e}", v).parse().unwrap_or(v);
+                    rounded.to_string()
+                }
+                Some(v) => v.to_string(),
+                None => "NULL".to_string(),
+            })
+            .collect());
+    }
+    let options = FormatOptions::default().with_null("NULL");
+    let formatter = ArrayFormatter::try_new(column.as_ref(), &options)?;
+    Ok((0..column.len())
+        .map(|i| formatter.value(i).to_string())
+        .collect())
+}
+
+// the rows only in `a` and only in `b`, both sorted, counting duplicates
+fn sorted_difference<'a>(
+    a: &'a [Vec<String>],
+    b: &'a [Vec<String>],
+) -> (Vec<&'a Vec<String>>, Vec<&'a Vec<String>>) {
+    let (mut only_a, mut only_b) = (vec![], vec![]);
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+            Ordering::Less => {
+                only_a.push(&a[i]);
+                i += 1;
+            }
+            Ordering::Greater => {
+                only_b.push(&b[j]);
+                j += 1;
+            }
+        }
+    }
+    only_a.extend(&a[i..]);
+    only_b.extend(&b[j..]);
+    (only_a, only_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Float64Array, Int32Array, Int64Array};
+
+    use super::*;
+    use crate::backend::OutputFormat;
+
+    #[tokio::test]
+    async fn test_verify_report() -> anyhow::Result<()> {
+        let reference = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "amount",
+                Arc::new(Float64Array::from(vec![0.1 + 0.2, 1.5, 2.5])) as ArrayRef,
+            ),
+        ])?;
+        // a narrower type, another row order and rounding errors
+        let same = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int32Array::from(vec![3, 1, 2])) as ArrayRef),
+            (
+                "amount",
+                Arc::new(Float64Array::from(vec![2.5, 0.3, 1.5])) as ArrayRef,
+            ),
+        ])?;
+        let other = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "amount",
+                Arc::new(Float64Array::from(vec![0.3, 1.0])) as ArrayRef,
+            ),
+        ])?;
+
+        let report = VerifyReport::try_new(vec![
+            ("datafusion".to_string(), vec![], Ok(reference)),
+            ("same".to_string(), vec![], Ok(same)),
+            ("other".to_string(), vec![], Ok(other)),
+            (
+                "polars".to_string(),
+                vec!["sales: file not found".to_string()],
+                Err(anyhow::anyhow!("table sales not found")),
+            ),
+        ])?;
+        let results = report.results();
+        assert!(results[1].differences.is_empty());
+        assert_eq!(
+            results[2].differences,
+            vec![
+                "3 rows on datafusion, 2 on other",
+                "2 rows only on datafusion",
+                "  (2, 1.5)",
+                "  (3, 2.5)",
+                "1 rows only on other",
+                "  (2, 1)",
+            ]
+        );
+        assert_eq!(results[3].replay_errors, vec!["sales: file not found"]);
+        assert!(!report.matches());
+
+        let output = OutputOptions {
+            format: OutputFormat::Csv,
+            max_rows: None,
+        };
+        let output = report.display(output).await?;
+        assert!(
+            output.ends_with(
+                "polars:\nquery failed: table sales not found\nfailed to register sales: file not found"
+            ),
+            "{}",
+            output
+        );
+        Ok(())
+    }
+}
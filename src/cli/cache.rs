@@ -0,0 +1,58 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{utils::human_bytes, Backend, CmdExecutor, ReplContext, ReplMsg};
+
+use super::{view::parse_assignment, ReplResult};
+
+#[derive(Debug, Parser)]
+pub struct CacheOpts {
+    #[arg(help = "The name of the in-memory table")]
+    pub name: String,
+    #[arg(long, help = "Replace an existing dataset of the same name")]
+    pub replace: bool,
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        help = "`=` followed by the SQL query"
+    )]
+    pub query: Vec<String>,
+}
+
+pub fn cache(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let query = args
+        .get_many::<String>("query")
+        .expect("expect query")
+        .cloned()
+        .collect();
+    let replace = args.get_flag("replace");
+
+    let (msg, rx) = ReplMsg::new(CacheOpts::new(name, query, replace));
+    Ok(ctx.send(msg, rx))
+}
+
+impl CacheOpts {
+    pub fn new(name: String, query: Vec<String>, replace: bool) -> Self {
+        Self {
+            name,
+            query,
+            replace,
+        }
+    }
+}
+
+impl CmdExecutor for CacheOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let (name, sql) = parse_assignment(&self.name, &self.query)?;
+        let size = backend.cache(&name, &sql, self.replace).await?;
+        Ok(format!(
+            "Cached {} in memory ({})",
+            name,
+            human_bytes(size as u64)
+        ))
+    }
+}
@@ -0,0 +1,126 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{backend::Rule, Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg, ReplReply};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct CheckOpts {
+    #[arg(help = "The name of the dataset or a local file")]
+    pub name: String,
+    #[arg(long, help = "The yaml file with the rules to check")]
+    pub rules: String,
+}
+
+pub fn check(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let rules = args
+        .get_one::<String>("rules")
+        .expect("expect rules")
+        .to_string();
+
+    let (msg, rx) = ReplMsg::new(CheckOpts::new(name, rules));
+    Ok(ctx.send(msg, rx))
+}
+
+impl CheckOpts {
+    pub fn new(name: String, rules: String) -> Self {
+        Self { name, rules }
+    }
+
+    // the report, which doesn't pass if any rule fails, for `taotie check` to exit non-zero
+    pub(crate) async fn check<T: Backend>(self, backend: &mut T) -> anyhow::Result<ReplReply> {
+        let rules = Rule::load(&self.rules)?;
+        let report = backend.check(&self.name, &rules).await?;
+        let passed = report.passed();
+        let failed = report.failed();
+        let output = report.display(backend.output()).await?;
+        let output = match failed {
+            0 => format!("{}\nAll {} checks passed", output, rules.len()),
+            n => format!("{}\n{} of {} checks failed", output, n, rules.len()),
+        };
+        Ok(ReplReply {
+            passed,
+            ..output.into()
+        })
+    }
+}
+
+impl CmdExecutor for CheckOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        Ok(self.check(backend).await?.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::DataFusionBackend,
+        test_utils::{connect_opts, TestDir},
+    };
+
+    #[tokio::test]
+    async fn test_check() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let users = dir.write(
+            "users.csv",
+            "id,age,status\n1,30,active\n2,200,active\n2,,gone\n",
+        );
+        let orders = dir.write("orders.csv", "id,user_id\n1,1\n2,3\n");
+        let mut backend = DataFusionBackend::new();
+        backend.connect(&connect_opts("users", &users)).await?;
+        backend.connect(&connect_opts("orders", &orders)).await?;
+
+        let rules = dir.write(
+            "users.yaml",
+            "- not_null: id\n\
+             - not_null: age\n\
+             - unique: id\n\
+             - range: { column: age, min: 0, max: 150 }\n\
+             - allowed_values: { column: status, values: [active] }\n\
+             - row_count: { min: 3 }\n",
+        );
+        let report = backend.check("users", &Rule::load(&rules)?).await?;
+        let failures: Vec<_> = report.results().iter().map(|r| r.failures).collect();
+        assert_eq!(failures, [0, 1, 1, 1, 1, 0]);
+        let reply = CheckOpts::new("users".into(), rules)
+            .check(&mut backend)
+            .await?;
+        assert!(!reply.passed);
+        assert!(
+            reply.output.ends_with("4 of 6 checks failed"),
+            "{}",
+            reply.output
+        );
+
+        let rules = dir.write(
+            "orders.yaml",
+            "- foreign_key: { column: user_id, dataset: users, references: id }\n",
+        );
+        let reply = CheckOpts::new("orders".into(), rules)
+            .check(&mut backend)
+            .await?;
+        assert!(!reply.passed);
+        assert!(
+            reply.output.ends_with("1 of 1 checks failed"),
+            "{}",
+            reply.output
+        );
+
+        let rules = dir.write("pass.yaml", "- not_null: id\n- row_count: { max: 3 }\n");
+        let reply = CheckOpts::new("users".into(), rules)
+            .check(&mut backend)
+            .await?;
+        assert!(reply.passed);
+        assert!(
+            reply.output.ends_with("All 2 checks passed"),
+            "{}",
+            reply.output
+        );
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
+use std::str::FromStr;
+
 use clap::{ArgMatches, Parser};
-use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::{
+    common::parsers::CompressionTypeVariant,
+    datasource::file_format::file_compression_type::FileCompressionType,
+};
 
 use crate::{Backend, CmdExecutor, ReplContext, ReplMsg};
 
@@ -20,6 +25,42 @@ pub struct FileOpts {
     pub compression: FileCompressionType,
 }
 
+impl DatasetConn {
+    pub fn location(&self) -> &str {
+        match self {
+            DatasetConn::Postgres(conn_str) => conn_str,
+            DatasetConn::Csv(file_opts) | DatasetConn::NdJson(file_opts) => &file_opts.filename,
+            DatasetConn::Parquet(filename) => filename,
+        }
+    }
+
+    pub fn format(&self) -> &'static str {
+        match self {
+            DatasetConn::Postgres(_) => "postgres",
+            DatasetConn::Csv(_) => "csv",
+            DatasetConn::Parquet(_) => "parquet",
+            DatasetConn::NdJson(_) => "ndjson",
+        }
+    }
+
+    pub fn compression(&self) -> Option<&'static str> {
+        match self {
+            DatasetConn::Csv(file_opts) | DatasetConn::NdJson(file_opts) => {
+                Some(file_opts.compression_name())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for DatasetConn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        verify_conn_str(s)
+    }
+}
+
 impl FileOpts {
     pub fn new(
         filename: impl Into<String>,
@@ -32,6 +73,25 @@ impl FileOpts {
             compression,
         }
     }
+
+    // the full extension including the compression suffix, e.g. `.csv.gz`
+    pub fn file_extension(&self) -> &str {
+        let ext = format!(".{}", self.ext);
+        match self.filename.rfind(&ext) {
+            Some(pos) => &self.filename[pos..],
+            None => "",
+        }
+    }
+
+    pub fn compression_name(&self) -> &'static str {
+        match self.compression.get_variant() {
+            CompressionTypeVariant::GZIP => "gzip",
+            CompressionTypeVariant::BZIP2 => "bzip2",
+            CompressionTypeVariant::XZ => "xz",
+            CompressionTypeVariant::ZSTD => "zstd",
+            CompressionTypeVariant::UNCOMPRESSED => "uncompressed",
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -74,7 +134,7 @@ impl CmdExecutor for ConnectOpts {
     }
 }
 
-fn verify_conn_str(s: &str) -> Result<DatasetConn, String> {
+pub(super) fn verify_conn_str(s: &str) -> Result<DatasetConn, String> {
     if s.starts_with("postgres://") {
         return Ok(DatasetConn::Postgres(s.to_owned()));
     }
@@ -135,4 +195,12 @@ mod tests {
         let opt = get_file_opt("foobar");
         assert!(opt.is_none());
     }
+
+    #[test]
+    fn test_file_extension() {
+        let opt = get_file_opt("data/foo.csv.gz").unwrap();
+        assert_eq!(opt.file_extension(), ".csv.gz");
+        let opt = get_file_opt("data/foo.ndjson").unwrap();
+        assert_eq!(opt.file_extension(), ".ndjson");
+    }
 }
@@ -0,0 +1,128 @@
+use clap::{ArgMatches, Parser, ValueEnum};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplMsg};
+
+use super::{connect::verify_conn_str, DatasetConn, ReplResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ParquetCodec {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Lz4,
+    Zstd,
+    Brotli,
+}
+
+#[derive(Debug, Parser)]
+pub struct ConvertOpts {
+    #[arg(
+        help = "The source: the name of a dataset or a local file (support: csv, parquet, json)"
+    )]
+    pub src: String,
+    #[arg(value_parser = verify_conn_str, help = "The destination file, the format and compression are detected from the extension")]
+    pub dst: DatasetConn,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Columns to partition the output by"
+    )]
+    pub partition_by: Vec<String>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Columns to sort the output by, append `:desc` for descending order"
+    )]
+    pub sort_by: Vec<String>,
+    #[arg(long, help = "The max number of rows in a parquet row group")]
+    pub row_group_size: Option<usize>,
+    #[arg(long, value_enum, help = "The compression codec of the parquet output")]
+    pub codec: Option<ParquetCodec>,
+}
+
+pub fn convert(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let src = args
+        .get_one::<String>("src")
+        .expect("expect src")
+        .to_string();
+    let dst = args
+        .get_one::<DatasetConn>("dst")
+        .expect("expect dst")
+        .to_owned();
+    let partition_by = args
+        .get_many::<String>("partition_by")
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default();
+    let sort_by = args
+        .get_many::<String>("sort_by")
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default();
+    let row_group_size = args.get_one::<usize>("row_group_size").copied();
+    let codec = args.get_one::<ParquetCodec>("codec").copied();
+
+    let (msg, rx) = ReplMsg::new(ConvertOpts::new(
+        src,
+        dst,
+        partition_by,
+        sort_by,
+        row_group_size,
+        codec,
+    ));
+    Ok(ctx.send(msg, rx))
+}
+
+impl ConvertOpts {
+    pub fn new(
+        src: String,
+        dst: DatasetConn,
+        partition_by: Vec<String>,
+        sort_by: Vec<String>,
+        row_group_size: Option<usize>,
+        codec: Option<ParquetCodec>,
+    ) -> Self {
+        Self {
+            src,
+            dst,
+            partition_by,
+            sort_by,
+            row_group_size,
+            codec,
+        }
+    }
+
+    pub(crate) fn verify(&self) -> anyhow::Result<()> {
+        if !matches!(self.dst, DatasetConn::Parquet(_))
+            && (self.row_group_size.is_some() || self.codec.is_some())
+        {
+            anyhow::bail!("--row-group-size and --codec are only supported for parquet output");
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for ConvertOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        self.verify()?;
+        let rows = backend.convert(&self).await?;
+        Ok(format!(
+            "Converted {} rows from {} to {}",
+            rows,
+            self.src,
+            self.dst.location()
+        ))
+    }
+}
+
+impl ParquetCodec {
+    // in the format of datafusion's `ParquetOptions::compression`
+    pub fn as_compression_str(&self) -> &'static str {
+        match self {
+            ParquetCodec::Uncompressed => "uncompressed",
+            ParquetCodec::Snappy => "snappy",
+            ParquetCodec::Gzip => "gzip(6)",
+            ParquetCodec::Lz4 => "lz4_raw",
+            ParquetCodec::Zstd => "zstd(3)",
+            ParquetCodec::Brotli => "brotli(4)",
+        }
+    }
+}
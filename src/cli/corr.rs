@@ -0,0 +1,61 @@
+use clap::{ArgMatches, Parser, ValueEnum};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CorrMethod {
+    Pearson,
+    Spearman,
+}
+
+#[derive(Debug, Parser)]
+pub struct CorrOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "The columns to correlate, defaults to all numeric columns"
+    )]
+    pub columns: Vec<String>,
+    #[arg(long, value_enum, default_value_t = CorrMethod::Pearson, help = "The correlation method")]
+    pub method: CorrMethod,
+}
+
+pub fn corr(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let columns = args
+        .get_many::<String>("columns")
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default();
+    let method = args
+        .get_one::<CorrMethod>("method")
+        .copied()
+        .expect("expect method");
+
+    let (msg, rx) = ReplMsg::new(CorrOpts::new(name, columns, method));
+    Ok(ctx.send(msg, rx))
+}
+
+impl CorrOpts {
+    pub fn new(name: String, columns: Vec<String>, method: CorrMethod) -> Self {
+        Self {
+            name,
+            columns,
+            method,
+        }
+    }
+}
+
+impl CmdExecutor for CorrOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let matrix = backend.corr(&self).await?;
+        matrix.display(output).await
+    }
+}
@@ -0,0 +1,60 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct CountsOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+    #[arg(
+        required = true,
+        help = "The column(s) to count the values (or combinations) of"
+    )]
+    pub columns: Vec<String>,
+    #[arg(
+        long,
+        default_value_t = 20,
+        help = "The number of most frequent values to show"
+    )]
+    pub top: usize,
+    #[arg(long, help = "Show the fraction of rows instead of the row count")]
+    pub normalize: bool,
+}
+
+pub fn counts(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let columns = args
+        .get_many::<String>("columns")
+        .expect("expect columns")
+        .cloned()
+        .collect();
+    let top = args.get_one::<usize>("top").copied().expect("expect top");
+    let normalize = args.get_flag("normalize");
+
+    let (msg, rx) = ReplMsg::new(CountsOpts::new(name, columns, top, normalize));
+    Ok(ctx.send(msg, rx))
+}
+
+impl CountsOpts {
+    pub fn new(name: String, columns: Vec<String>, top: usize, normalize: bool) -> Self {
+        Self {
+            name,
+            columns,
+            top,
+            normalize,
+        }
+    }
+}
+
+impl CmdExecutor for CountsOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let counts = backend.counts(&self).await?;
+        counts.display(output).await
+    }
+}
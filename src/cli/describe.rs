@@ -8,6 +8,12 @@ use super::ReplResult;
 pub struct DescribeOpts {
     #[arg(help = "The name of the dataset")]
     pub name: String,
+
+    #[arg(
+        long,
+        help = "Add a sparkline of the distribution of each numeric column"
+    )]
+    pub sparkline: bool,
 }
 
 pub fn describe(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
@@ -16,19 +22,22 @@ pub fn describe(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
         .expect("expect name")
         .to_string();
 
-    let (msg, rx) = ReplMsg::new(DescribeOpts::new(name));
+    let sparkline = args.get_flag("sparkline");
+
+    let (msg, rx) = ReplMsg::new(DescribeOpts::new(name, sparkline));
     Ok(ctx.send(msg, rx))
 }
 
 impl DescribeOpts {
-    pub fn new(name: String) -> Self {
-        Self { name }
+    pub fn new(name: String, sparkline: bool) -> Self {
+        Self { name, sparkline }
     }
 }
 
 impl CmdExecutor for DescribeOpts {
     async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
-        let df = backend.describe(&self.name).await?;
-        df.display().await
+        let output = backend.output();
+        let df = backend.describe(&self.name, self.sparkline).await?;
+        df.display(output).await
     }
 }
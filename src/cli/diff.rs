@@ -0,0 +1,72 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct DiffOpts {
+    #[arg(help = "The name of the old dataset (or a local file)")]
+    pub a: String,
+    #[arg(help = "The name of the new dataset (or a local file)")]
+    pub b: String,
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        help = "The key columns to match rows by"
+    )]
+    pub key: Vec<String>,
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "The number of sample rows to show for each kind of difference"
+    )]
+    pub sample: usize,
+    #[arg(long, help = "Register the full diff as a view with the given name")]
+    pub into: Option<String>,
+}
+
+pub fn diff(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let a = args.get_one::<String>("a").expect("expect a").to_string();
+    let b = args.get_one::<String>("b").expect("expect b").to_string();
+    let key = args
+        .get_many::<String>("key")
+        .expect("expect key")
+        .cloned()
+        .collect();
+    let sample = args
+        .get_one::<usize>("sample")
+        .copied()
+        .expect("expect sample");
+    let into = args.get_one::<String>("into").map(|s| s.to_string());
+
+    let (msg, rx) = ReplMsg::new(DiffOpts::new(a, b, key, sample, into));
+    Ok(ctx.send(msg, rx))
+}
+
+impl DiffOpts {
+    pub fn new(
+        a: String,
+        b: String,
+        key: Vec<String>,
+        sample: usize,
+        into: Option<String>,
+    ) -> Self {
+        Self {
+            a,
+            b,
+            key,
+            sample,
+            into,
+        }
+    }
+}
+
+impl CmdExecutor for DiffOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let diff = backend.diff(&self).await?;
+        diff.display(output).await
+    }
+}
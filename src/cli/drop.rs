@@ -0,0 +1,34 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct DropOpts {
+    #[arg(help = "The name of the dataset or view")]
+    pub name: String,
+}
+
+pub fn drop_dataset(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+
+    let (msg, rx) = ReplMsg::new(DropOpts::new(name));
+    Ok(ctx.send(msg, rx))
+}
+
+impl DropOpts {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+}
+
+impl CmdExecutor for DropOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        backend.deregister(&self.name).await?;
+        Ok(format!("Dropped dataset: {}", self.name))
+    }
+}
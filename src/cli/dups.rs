@@ -0,0 +1,93 @@
+use clap::{ArgMatches, Parser, ValueEnum};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum KeepRow {
+    First,
+    Last,
+}
+
+#[derive(Debug, Parser)]
+pub struct DupsOpts {
+    #[arg(help = "The name of the dataset (or a local file)")]
+    pub name: String,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "The key columns, defaults to all columns to find exact duplicates"
+    )]
+    pub key: Vec<String>,
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "The number of most repeated keys to show"
+    )]
+    pub top: usize,
+    #[arg(
+        long,
+        help = "Register a deduplicated view with the given name, keeping one row per key"
+    )]
+    pub into: Option<String>,
+    #[arg(long, value_enum, default_value_t = KeepRow::First, help = "Which row to keep per key with --into")]
+    pub keep: KeepRow,
+    #[arg(
+        long,
+        help = "The column ordering the rows of a key, required by `--keep last`"
+    )]
+    pub order_by: Option<String>,
+}
+
+pub fn dups(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let key = args
+        .get_many::<String>("key")
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default();
+    let top = args.get_one::<usize>("top").copied().expect("expect top");
+    let into = args.get_one::<String>("into").map(|s| s.to_string());
+    let keep = args
+        .get_one::<KeepRow>("keep")
+        .copied()
+        .expect("expect keep");
+    let order_by = args.get_one::<String>("order_by").map(|s| s.to_string());
+
+    let (msg, rx) = ReplMsg::new(DupsOpts::new(name, key, top, into, keep, order_by));
+    Ok(ctx.send(msg, rx))
+}
+
+impl DupsOpts {
+    pub fn new(
+        name: String,
+        key: Vec<String>,
+        top: usize,
+        into: Option<String>,
+        keep: KeepRow,
+        order_by: Option<String>,
+    ) -> Self {
+        Self {
+            name,
+            key,
+            top,
+            into,
+            keep,
+            order_by,
+        }
+    }
+}
+
+impl CmdExecutor for DupsOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        if self.keep == KeepRow::Last && self.order_by.is_none() {
+            anyhow::bail!("--keep last requires --order-by");
+        }
+        let dups = backend.dups(&self).await?;
+        dups.display(output).await
+    }
+}
@@ -0,0 +1,37 @@
+use super::ReplResult;
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct ExplainOpts {
+    #[arg(help = "The SQL query")]
+    pub query: String,
+
+    #[arg(long, help = "Run the query and show the metrics of each operator")]
+    pub analyze: bool,
+}
+
+pub fn explain(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let query = args
+        .get_one::<String>("query")
+        .expect("expect query")
+        .to_string();
+    let analyze = args.get_flag("analyze");
+
+    let (msg, rx) = ReplMsg::new(ExplainOpts::new(query, analyze));
+    Ok(ctx.send(msg, rx))
+}
+
+impl ExplainOpts {
+    pub fn new(query: String, analyze: bool) -> Self {
+        Self { query, analyze }
+    }
+}
+
+impl CmdExecutor for ExplainOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let plan = backend.explain(&self.query, self.analyze).await?;
+        plan.display(output).await
+    }
+}
@@ -31,7 +31,8 @@ impl HeadOpts {
 
 impl CmdExecutor for HeadOpts {
     async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
         let df = backend.head(&self.name, self.n.unwrap_or(5)).await?;
-        df.display().await
+        df.display(output).await
     }
 }
@@ -0,0 +1,48 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct HistOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+    #[arg(help = "The numeric or temporal column")]
+    pub column: String,
+    #[arg(
+        long,
+        default_value_t = 20,
+        help = "The number of bins for numeric columns, temporal columns are binned by day, week, month or year"
+    )]
+    pub bins: usize,
+}
+
+pub fn hist(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let column = args
+        .get_one::<String>("column")
+        .expect("expect column")
+        .to_string();
+    let bins = args.get_one::<usize>("bins").copied().expect("expect bins");
+
+    let (msg, rx) = ReplMsg::new(HistOpts::new(name, column, bins));
+    Ok(ctx.send(msg, rx))
+}
+
+impl HistOpts {
+    pub fn new(name: String, column: String, bins: usize) -> Self {
+        Self { name, column, bins }
+    }
+}
+
+impl CmdExecutor for HistOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let hist = backend.hist(&self.name, &self.column, self.bins).await?;
+        hist.display(output).await
+    }
+}
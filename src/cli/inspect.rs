@@ -0,0 +1,35 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct InspectOpts {
+    #[arg(help = "The name of a parquet dataset, or the path to a parquet file or directory")]
+    pub target: String,
+}
+
+pub fn inspect(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let target = args
+        .get_one::<String>("target")
+        .expect("expect target")
+        .to_string();
+
+    let (msg, rx) = ReplMsg::new(InspectOpts::new(target));
+    Ok(ctx.send(msg, rx))
+}
+
+impl InspectOpts {
+    pub fn new(target: String) -> Self {
+        Self { target }
+    }
+}
+
+impl CmdExecutor for InspectOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let inspection = backend.inspect(&self.target).await?;
+        inspection.display(output).await
+    }
+}
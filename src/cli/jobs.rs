@@ -0,0 +1,273 @@
+use std::iter;
+
+use super::{ReplCommand, ReplResult};
+use crate::{Backend, CmdExecutor, ReplContext};
+use clap::{parser::ValueSource, Arg, ArgAction, ArgMatches, Command, CommandFactory, Parser};
+
+// the hidden last argument of commands, which run in the background when it is `&`
+const BACKGROUND: &str = "background";
+
+#[derive(Debug, Parser)]
+pub struct BgOpts {
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        help = "The command to run in the background, e.g. `convert sales out.parquet`"
+    )]
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct JobsOpts;
+
+#[derive(Debug, Parser)]
+pub struct WaitOpts {
+    #[arg(help = "The id of the job")]
+    pub id: u64,
+}
+
+#[derive(Debug, Parser)]
+pub struct CancelOpts {
+    #[arg(help = "The id of the job")]
+    pub id: u64,
+}
+
+pub fn bg(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let command: Vec<String> = args
+        .get_many::<String>("command")
+        .expect("expect command")
+        .cloned()
+        .collect();
+    spawn(command, ctx)
+}
+
+/// Run a command, or start it as a job like `bg` when its line ends with `&`.
+pub fn run_or_spawn(
+    name: &str,
+    args: ArgMatches,
+    ctx: &mut ReplContext,
+    callback: fn(ArgMatches, &mut ReplContext) -> ReplResult,
+) -> ReplResult {
+    let cmd = ReplCommand::command();
+    match cmd
+        .find_subcommand(name)
+        .and_then(|c| background_words(c, &args))
+    {
+        Some(words) => spawn(iter::once(name.to_string()).chain(words).collect(), ctx),
+        None => callback(args, ctx),
+    }
+}
+
+/// Let each command take a trailing `&`, a hidden last positional, unless its last positional
+/// takes several values, of which `&` is then the last one.
+pub fn with_background(cmd: Command) -> Command {
+    let multiple = cmd
+        .get_positionals()
+        .any(|a| matches!(a.get_action(), ArgAction::Append));
+    if multiple {
+        return cmd;
+    }
+    cmd.arg(Arg::new(BACKGROUND).value_parser(["&"]).hide(true))
+}
+
+// the words of the command line without the trailing `&`, none without it
+fn background_words(cmd: &Command, args: &ArgMatches) -> Option<Vec<String>> {
+    let mut background = args.try_contains_id(BACKGROUND).unwrap_or(false);
+    let mut words = vec![];
+    for arg in cmd.get_arguments() {
+        let id = arg.get_id().as_str();
+        if id == BACKGROUND || args.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let mut values: Vec<String> = args
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|v| v.to_string_lossy().to_string())
+            .collect();
+        if arg.is_positional() {
+            let append = matches!(arg.get_action(), ArgAction::Append);
+            if append && values.last().is_some_and(|v| v == "&") {
+                values.pop();
+                background = true;
+            }
+            words.extend(values);
+            continue;
+        }
+        let flag = match arg.get_long() {
+            Some(long) => format!("--{}", long),
+            None => format!("-{}", arg.get_short()?),
+        };
+        match arg.get_action() {
+            ArgAction::SetTrue | ArgAction::SetFalse | ArgAction::Count => words.push(flag),
+            _ => {
+                for value in values {
+                    words.push(flag.clone());
+                    words.push(value);
+                }
+            }
+        }
+    }
+    background.then_some(words)
+}
+
+fn spawn(command: Vec<String>, ctx: &mut ReplContext) -> ReplResult {
+    let mut cmd = match ReplCommand::try_parse_from(
+        iter::once("taotie").chain(command.iter().map(|s| s.as_str())),
+    ) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(None);
+        }
+    };
+    // the job runs against a copy of the catalog, so changes to it would be lost
+    if !cmd.is_read_only() {
+        eprintln!("{} can't run in the background", command[0]);
+        return Ok(None);
+    }
+    // bind now to report an address in use here rather than in the job
+    if let ReplCommand::Serve(opts) = &mut cmd {
+        if let Err(e) = opts.bind() {
+            eprintln!("{}", e);
+            return Ok(None);
+        }
+    }
+    let command = command.join(" ");
+    let id = ctx.spawn_job(command.clone(), cmd);
+    Ok(Some(format!("[{}] {}", id, command)))
+}
+
+pub fn jobs(_args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let output = ctx.output();
+    match ctx.jobs.list(&output) {
+        Ok(jobs) => Ok(Some(jobs)),
+        Err(e) => {
+            eprintln!("Failed to list jobs: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+pub fn wait(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let id = *args.get_one::<u64>("id").expect("expect id");
+    match ctx.jobs.wait(id) {
+        Ok(output) => Ok(Some(output)),
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(None)
+        }
+    }
+}
+
+pub fn cancel(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let id = *args.get_one::<u64>("id").expect("expect id");
+    match ctx.jobs.cancel(id) {
+        Ok(()) => Ok(Some(format!("Cancelling job {}", id))),
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(None)
+        }
+    }
+}
+
+// jobs live in the REPL rather than the backend, e.g. they can't run from the rc file
+impl CmdExecutor for BgOpts {
+    async fn execute<T: Backend>(self, _backend: &mut T) -> anyhow::Result<String> {
+        anyhow::bail!("bg is only available in the REPL")
+    }
+}
+
+impl CmdExecutor for JobsOpts {
+    async fn execute<T: Backend>(self, _backend: &mut T) -> anyhow::Result<String> {
+        anyhow::bail!("jobs is only available in the REPL")
+    }
+}
+
+impl CmdExecutor for WaitOpts {
+    async fn execute<T: Backend>(self, _backend: &mut T) -> anyhow::Result<String> {
+        anyhow::bail!("wait is only available in the REPL")
+    }
+}
+
+impl CmdExecutor for CancelOpts {
+    async fn execute<T: Backend>(self, _backend: &mut T) -> anyhow::Result<String> {
+        anyhow::bail!("cancel is only available in the REPL")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::convert,
+        repl_commands,
+        test_utils::{connect_opts, TestDir},
+        ReplMsg,
+    };
+
+    // the name and arguments of a REPL line, as the REPL hands them to the callback
+    fn matches(line: &str) -> (String, ArgMatches) {
+        let args = repl_commands()
+            .try_get_matches_from(iter::once("taotie").chain(line.split_whitespace()))
+            .unwrap();
+        let (name, args) = args.subcommand().unwrap();
+        (name.to_string(), args.clone())
+    }
+
+    fn words(line: &str) -> Option<Vec<String>> {
+        let (name, args) = matches(line);
+        background_words(
+            ReplCommand::command().find_subcommand(&name).unwrap(),
+            &args,
+        )
+    }
+
+    #[test]
+    fn test_background_words() {
+        assert_eq!(words("convert t out.parquet"), None);
+        assert_eq!(
+            words("convert t out.parquet --sort-by a,b --codec zstd &").unwrap(),
+            vec![
+                "t",
+                "out.parquet",
+                "--sort-by",
+                "a",
+                "--sort-by",
+                "b",
+                "--codec",
+                "zstd"
+            ]
+        );
+        // `&` is the last of the values of a positional taking several
+        assert_eq!(words("counts t a b"), None);
+        assert_eq!(words("counts t a b &").unwrap(), vec!["t", "a", "b"]);
+        assert!(repl_commands()
+            .try_get_matches_from(["taotie", "head", "t", "x"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_background_convert() {
+        let dir = TestDir::new();
+        let src = dir.write("ids.csv", "id,name\n1,a\n2,b\n3,c\n");
+        let dst = dir.path("ids.parquet");
+        let mut ctx = ReplContext::new();
+        let (msg, rx) = ReplMsg::new(connect_opts("ids", &src));
+        ctx.send(msg, rx).unwrap();
+
+        let (name, args) = matches(&format!("convert ids {} &", dst));
+        let started = run_or_spawn(&name, args, &mut ctx, convert)
+            .unwrap()
+            .unwrap();
+        assert_eq!(started, format!("[1] convert ids {}", dst));
+        let output = ctx.jobs.wait(1).unwrap();
+        assert!(
+            output.starts_with("Converted 3 rows from ids to"),
+            "{}",
+            output
+        );
+        assert!(std::path::Path::new(&dst).exists());
+    }
+}
@@ -5,16 +5,31 @@ use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
 use super::ReplResult;
 
 #[derive(Parser, Debug)]
-pub struct ListOpts;
+pub struct ListOpts {
+    #[arg(
+        long,
+        help = "Count rows exactly for datasets without row count metadata (e.g. csv, json)"
+    )]
+    pub exact: bool,
+}
+
+pub fn list(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let exact = args.get_flag("exact");
 
-pub fn list(_args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
-    let (msg, rx) = ReplMsg::new(ListOpts);
+    let (msg, rx) = ReplMsg::new(ListOpts::new(exact));
     Ok(ctx.send(msg, rx))
 }
 
+impl ListOpts {
+    pub fn new(exact: bool) -> Self {
+        Self { exact }
+    }
+}
+
 impl CmdExecutor for ListOpts {
     async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
-        let df = backend.list().await?;
-        df.display().await
+        let output = backend.output();
+        let df = backend.list(self.exact).await?;
+        df.display(output).await
     }
 }
@@ -1,28 +1,97 @@
+pub use cache::CacheOpts;
+pub use check::CheckOpts;
 use clap::Parser;
 pub use connect::ConnectOpts;
+pub use convert::{ConvertOpts, ParquetCodec};
+pub use corr::{CorrMethod, CorrOpts};
+pub use counts::CountsOpts;
 pub use describe::DescribeOpts;
+pub use diff::DiffOpts;
+pub use drop::DropOpts;
+pub use dups::{DupsOpts, KeepRow};
 use enum_dispatch::enum_dispatch;
 pub use exit::ExitOpts;
+pub use explain::ExplainOpts;
 pub use head::HeadOpts;
+pub use hist::HistOpts;
+pub use inspect::InspectOpts;
+pub use jobs::{BgOpts, CancelOpts, JobsOpts, WaitOpts};
 pub use list::ListOpts;
+pub use query::{QueryAction, QueryOpts};
+pub use rows::RowsOpts;
+pub use sample::SampleOpts;
 pub use schema::SchemaOpts;
+pub use schema_diff::SchemaDiffOpts;
+pub use serve::ServeOpts;
+pub use set::SetOpts;
+pub use show::ShowOpts;
 pub use sql::SqlOpts;
+pub use tail::TailOpts;
+pub use use_backend::UseOpts;
+pub use verify::VerifyOpts;
+pub use view::LetOpts;
 
-pub use connect::{connect, DatasetConn};
+pub use cache::cache;
+pub use check::check;
+pub use connect::{connect, DatasetConn, FileOpts};
+pub use convert::convert;
+pub use corr::corr;
+pub use counts::counts;
 pub use describe::describe;
+pub use diff::diff;
+pub use drop::drop_dataset;
+pub use dups::dups;
 pub use exit::exit;
+pub use explain::explain;
 pub use head::head;
+pub use hist::hist;
+pub use inspect::inspect;
+pub use jobs::{bg, cancel, jobs, run_or_spawn, wait, with_background};
 pub use list::list;
+pub use query::query;
+pub use rows::rows;
+pub use sample::sample;
 pub use schema::schema;
+pub use schema_diff::schema_diff;
+pub use serve::serve;
+pub use set::set;
+pub use show::show;
 pub use sql::sql;
+pub use tail::tail;
+pub use use_backend::use_backend;
+pub use verify::verify;
+pub use view::view;
 
+mod cache;
+mod check;
 mod connect;
+mod convert;
+mod corr;
+mod counts;
 mod describe;
+mod diff;
+mod drop;
+mod dups;
 mod exit;
+mod explain;
 mod head;
+mod hist;
+mod inspect;
+mod jobs;
 mod list;
+mod query;
+mod rows;
+mod sample;
 mod schema;
+mod schema_diff;
+mod serve;
+mod set;
+mod show;
 mod sql;
+mod tail;
+mod use_backend;
+mod verify;
+mod view;
 
 type ReplResult = Result<Option<String>, reedline_repl_rs::Error>;
 
@@ -42,8 +111,123 @@ pub enum ReplCommand {
     Describe(DescribeOpts),
     #[command(about = "Show first few rows of a dataset")]
     Head(HeadOpts),
+    #[command(about = "Show last few rows of a dataset")]
+    Tail(TailOpts),
+    #[command(about = "Show a range of rows of a dataset")]
+    Rows(RowsOpts),
     #[command(about = "Query a dataset using given SQL")]
     Sql(SqlOpts),
+    #[command(
+        name = "explain",
+        about = "Show the logical and physical plans of a SQL query"
+    )]
+    Explain(ExplainOpts),
+    #[command(name = "let", about = "Register the result of a SQL query as a view")]
+    Let(LetOpts),
+    #[command(
+        name = "cache",
+        about = "Materialise the result of a SQL query as an in-memory table"
+    )]
+    Cache(CacheOpts),
+    #[command(name = "drop", about = "Unregister a dataset or view")]
+    Drop(DropOpts),
+    #[command(
+        name = "inspect",
+        about = "Show the row groups, column chunks and metadata of a parquet file"
+    )]
+    Inspect(InspectOpts),
+    #[command(name = "convert", about = "Convert a dataset to another format")]
+    Convert(ConvertOpts),
+    #[command(name = "schema-diff", about = "Compare the schemas of two datasets")]
+    SchemaDiff(SchemaDiffOpts),
+    #[command(name = "diff", about = "Compare the rows of two datasets by key")]
+    Diff(DiffOpts),
+    #[command(name = "dups", about = "Find duplicate rows, exact or by key")]
+    Dups(DupsOpts),
+    #[command(
+        name = "counts",
+        about = "Count the most frequent values of one or more columns"
+    )]
+    Counts(CountsOpts),
+    #[command(
+        name = "hist",
+        about = "Show a histogram of a numeric or temporal column"
+    )]
+    Hist(HistOpts),
+    #[command(
+        name = "corr",
+        about = "Show the correlation matrix of numeric columns"
+    )]
+    Corr(CorrOpts),
+    #[command(
+        name = "sample",
+        about = "Show a reproducible random sample of a dataset"
+    )]
+    Sample(SampleOpts),
+    #[command(name = "check", about = "Check a dataset against data quality rules")]
+    Check(CheckOpts),
+    #[command(
+        name = "verify",
+        about = "Run a query on every backend and compare the results"
+    )]
+    Verify(VerifyOpts),
+    #[command(
+        name = "set",
+        about = "Set an option of the session, e.g. `set timing on`"
+    )]
+    Set(SetOpts),
+    #[command(name = "show", about = "Show an option of the session, or `show all`")]
+    Show(ShowOpts),
+    #[command(
+        name = "serve",
+        about = "Serve the registered datasets to other clients, as a background job"
+    )]
+    Serve(ServeOpts),
+    #[command(
+        name = "bg",
+        about = "Run a command in the background, e.g. `bg convert sales out.parquet`"
+    )]
+    Bg(BgOpts),
+    #[command(name = "jobs", about = "List the background jobs")]
+    Jobs(JobsOpts),
+    #[command(name = "wait", about = "Wait for a background job and show its output")]
+    Wait(WaitOpts),
+    #[command(name = "cancel", about = "Cancel a background job")]
+    Cancel(CancelOpts),
+    #[command(
+        name = "use",
+        about = "Switch the backend, e.g. `use backend polars`, keeping the datasets"
+    )]
+    Use(UseOpts),
+    #[command(
+        name = "query",
+        about = "Save and run queries with parameters, e.g. `query run monthly start=2024-01-01`"
+    )]
+    Query(QueryOpts),
     #[command(name = "exit", about = "exit")]
     Exit(ExitOpts),
 }
+
+impl ReplCommand {
+    /// Whether the command leaves the catalog and the session unchanged, so that it can run in
+    /// the background.
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            ReplCommand::Connect(_)
+            | ReplCommand::Let(_)
+            | ReplCommand::Cache(_)
+            | ReplCommand::Drop(_)
+            | ReplCommand::Set(_)
+            | ReplCommand::Bg(_)
+            | ReplCommand::Jobs(_)
+            | ReplCommand::Wait(_)
+            | ReplCommand::Cancel(_)
+            | ReplCommand::Use(_)
+            | ReplCommand::Exit(_) => false,
+            ReplCommand::Diff(opts) => opts.into.is_none(),
+            ReplCommand::Dups(opts) => opts.into.is_none(),
+            ReplCommand::Sample(opts) => opts.into.is_none(),
+            _ => true,
+        }
+    }
+}
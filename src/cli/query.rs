@@ -0,0 +1,113 @@
+use std::ffi::OsStr;
+
+use super::ReplResult;
+use crate::{
+    queries::{bind, SavedQueries},
+    Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg,
+};
+use clap::{
+    builder::{PossibleValue, StringValueParser, TypedValueParser},
+    Arg, ArgMatches, Command, FromArgMatches, Parser, Subcommand,
+};
+
+#[derive(Debug, Parser)]
+pub struct QueryOpts {
+    #[command(subcommand)]
+    pub action: QueryAction,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum QueryAction {
+    #[command(
+        about = "Save a query, e.g. `query save monthly \"select ... where day >= $start\"`"
+    )]
+    Save {
+        #[arg(help = "The name of the query")]
+        name: String,
+        #[arg(help = "The SQL query, with `$name` parameters")]
+        sql: String,
+    },
+    #[command(about = "Run a saved query, e.g. `query run monthly start=2024-01-01`")]
+    Run {
+        #[arg(value_parser = QueryName, help = "The name of the query")]
+        name: String,
+        #[arg(value_parser = parse_param, help = "The parameters, as name=value or name:type=value, e.g. code:str=007")]
+        params: Vec<(String, String)>,
+    },
+    #[command(about = "List the saved queries")]
+    List,
+    #[command(about = "Show a saved query")]
+    Show {
+        #[arg(value_parser = QueryName, help = "The name of the query")]
+        name: String,
+    },
+}
+
+// accepts any name, offering the saved ones for tab completion
+#[derive(Debug, Clone)]
+struct QueryName;
+
+pub fn query(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    // the REPL has checked the arguments against the same definition, so only the action can
+    // be missing
+    let opts = QueryOpts::from_arg_matches(&args).map_err(|_| {
+        reedline_repl_rs::Error::MissingRequiredArgument("query".to_string(), "action".to_string())
+    })?;
+
+    let (msg, rx) = ReplMsg::new(opts);
+    Ok(ctx.send(msg, rx))
+}
+
+impl CmdExecutor for QueryOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let mut queries = SavedQueries::load()?;
+        match self.action {
+            QueryAction::Save { name, sql } => {
+                let replaced = queries.insert(&name, &sql)?;
+                queries.save()?;
+                match replaced {
+                    true => Ok(format!("Replaced query {}", name)),
+                    false => Ok(format!("Saved query {}", name)),
+                }
+            }
+            QueryAction::Run { name, params } => {
+                let sql = bind(queries.get(&name)?, &params)?;
+                let df = backend.sql(&sql).await?;
+                df.display(output).await
+            }
+            QueryAction::List => queries.list(),
+            QueryAction::Show { name } => Ok(queries.get(&name)?.to_string()),
+        }
+    }
+}
+
+impl TypedValueParser for QueryName {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        StringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    // read on every completion, so that queries saved in this session are offered
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let queries = SavedQueries::load().ok()?;
+        let names: Vec<_> = queries
+            .names()
+            .map(|name| PossibleValue::new(name.to_string()))
+            .collect();
+        Some(Box::new(names.into_iter()))
+    }
+}
+
+fn parse_param(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("invalid parameter: {}, expect name=value", s)),
+    }
+}
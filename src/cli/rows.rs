@@ -0,0 +1,91 @@
+use std::ops::Range;
+
+use super::ReplResult;
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct RowsOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+
+    #[arg(value_parser = parse_row_range, help = "The rows to show, e.g. `1000..1020` (end exclusive)")]
+    pub range: Range<usize>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Columns to sort by before slicing, append `:desc` for descending order"
+    )]
+    pub order_by: Vec<String>,
+}
+
+pub fn rows(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+
+    let range = args
+        .get_one::<Range<usize>>("range")
+        .expect("expect range")
+        .clone();
+    let order_by = args
+        .get_many::<String>("order_by")
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default();
+
+    let (msg, rx) = ReplMsg::new(RowsOpts::new(name, range, order_by));
+    Ok(ctx.send(msg, rx))
+}
+
+impl RowsOpts {
+    pub fn new(name: String, range: Range<usize>, order_by: Vec<String>) -> Self {
+        Self {
+            name,
+            range,
+            order_by,
+        }
+    }
+}
+
+impl CmdExecutor for RowsOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let df = backend
+            .rows(&self.name, self.range.clone(), &self.order_by)
+            .await?;
+        df.display(output).await
+    }
+}
+
+fn parse_row_range(s: &str) -> Result<Range<usize>, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("invalid row range: {}, expect `start..end`", s))?;
+    let start = match start {
+        "" => 0,
+        v => v.parse().map_err(|_| format!("invalid row index: {}", v))?,
+    };
+    let end = end
+        .parse()
+        .map_err(|_| format!("invalid row index: {}", end))?;
+    if start > end {
+        return Err(format!("invalid row range: {}, start is after end", s));
+    }
+    Ok(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_row_range() {
+        assert_eq!(parse_row_range("1000..1020"), Ok(1000..1020));
+        assert_eq!(parse_row_range("..20"), Ok(0..20));
+        assert!(parse_row_range("20..10").is_err());
+        assert!(parse_row_range("10..").is_err());
+        assert!(parse_row_range("10").is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct SampleOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+    #[arg(
+        short,
+        long,
+        conflicts_with = "fraction",
+        help = "The number of rows to sample (per group with --stratify-by), defaults to 100"
+    )]
+    pub n: Option<usize>,
+    #[arg(long, help = "The fraction of rows to sample, in (0, 1]")]
+    pub fraction: Option<f64>,
+    #[arg(long, help = "The seed to make the sample reproducible")]
+    pub seed: Option<u64>,
+    #[arg(long, help = "Sample each group of the given column separately")]
+    pub stratify_by: Option<String>,
+    #[arg(
+        long,
+        help = "Register the sample as an in-memory dataset with the given name"
+    )]
+    pub into: Option<String>,
+}
+
+pub fn sample(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let n = args.get_one::<usize>("n").copied();
+    let fraction = args.get_one::<f64>("fraction").copied();
+    let seed = args.get_one::<u64>("seed").copied();
+    let stratify_by = args.get_one::<String>("stratify_by").map(|s| s.to_string());
+    let into = args.get_one::<String>("into").map(|s| s.to_string());
+
+    let (msg, rx) = ReplMsg::new(SampleOpts::new(name, n, fraction, seed, stratify_by, into));
+    Ok(ctx.send(msg, rx))
+}
+
+impl SampleOpts {
+    pub fn new(
+        name: String,
+        n: Option<usize>,
+        fraction: Option<f64>,
+        seed: Option<u64>,
+        stratify_by: Option<String>,
+        into: Option<String>,
+    ) -> Self {
+        Self {
+            name,
+            n,
+            fraction,
+            seed,
+            stratify_by,
+            into,
+        }
+    }
+}
+
+impl CmdExecutor for SampleOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let sample = backend.sample(&self).await?;
+        sample.display(output).await
+    }
+}
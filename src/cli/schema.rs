@@ -26,7 +26,8 @@ impl SchemaOpts {
 
 impl CmdExecutor for SchemaOpts {
     async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
         let df = backend.schema(&self.name).await?;
-        df.display().await
+        df.display(output).await
     }
 }
@@ -0,0 +1,36 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{backend::SchemaDiff, Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct SchemaDiffOpts {
+    #[arg(help = "The name of the old dataset (or a local file)")]
+    pub a: String,
+    #[arg(help = "The name of the new dataset (or a local file)")]
+    pub b: String,
+}
+
+pub fn schema_diff(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let a = args.get_one::<String>("a").expect("expect a").to_string();
+    let b = args.get_one::<String>("b").expect("expect b").to_string();
+
+    let (msg, rx) = ReplMsg::new(SchemaDiffOpts::new(a, b));
+    Ok(ctx.send(msg, rx))
+}
+
+impl SchemaDiffOpts {
+    pub fn new(a: String, b: String) -> Self {
+        Self { a, b }
+    }
+}
+
+impl CmdExecutor for SchemaDiffOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let a = backend.schema(&self.a).await?;
+        let b = backend.schema(&self.b).await?;
+        SchemaDiff::new(&a, &b).display(output).await
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fmt, net::TcpListener};
+
+use super::ReplResult;
+use crate::{Backend, CmdExecutor, ReplContext};
+use clap::{ArgGroup, ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("server").required(true).multiple(true)))]
+pub struct ServeOpts {
+    #[arg(
+        long,
+        group = "server",
+        help = "Serve the datasets over the Postgres wire protocol, e.g. 127.0.0.1:5433"
+    )]
+    pub pg: Option<String>,
+    #[arg(
+        long,
+        group = "server",
+        help = "Serve the datasets over Arrow Flight SQL, e.g. 127.0.0.1:50051"
+    )]
+    pub flight: Option<String>,
+    #[arg(
+        long,
+        group = "server",
+        help = "Serve the datasets over an HTTP/JSON API, e.g. 127.0.0.1:8080"
+    )]
+    pub http: Option<String>,
+    // bound before the job starts, so that its output shows the actual addresses
+    #[arg(skip)]
+    pub listeners: Listeners,
+}
+
+#[derive(Debug, Default)]
+pub struct Listeners {
+    pub pg: Option<TcpListener>,
+    pub flight: Option<TcpListener>,
+    pub http: Option<TcpListener>,
+}
+
+// the server runs until cancelled, so run it as a job rather than block the REPL
+pub fn serve(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let pg = args.get_one::<String>("pg").map(|s| s.to_string());
+    let flight = args.get_one::<String>("flight").map(|s| s.to_string());
+    let http = args.get_one::<String>("http").map(|s| s.to_string());
+
+    let mut opts = ServeOpts::new(pg, flight, http);
+    if let Err(e) = opts.bind() {
+        eprintln!("{}", e);
+        return Ok(None);
+    }
+    let command = format!("serve {}", opts);
+    let id = ctx.spawn_job(command.clone(), opts.into());
+    Ok(Some(format!("[{}] {}", id, command)))
+}
+
+impl ServeOpts {
+    pub fn new(pg: Option<String>, flight: Option<String>, http: Option<String>) -> Self {
+        Self {
+            pg,
+            flight,
+            http,
+            listeners: Listeners::default(),
+        }
+    }
+
+    /// Bind the addresses, replacing them with the bound ones, e.g. for port 0.
+    pub fn bind(&mut self) -> anyhow::Result<()> {
+        let servers = [
+            (&mut self.pg, &mut self.listeners.pg),
+            (&mut self.flight, &mut self.listeners.flight),
+            (&mut self.http, &mut self.listeners.http),
+        ];
+        for (addr, listener) in servers {
+            if let Some(addr) = addr {
+                let bound = TcpListener::bind(addr.as_str())
+                    .map_err(|e| anyhow::anyhow!("failed to bind {}: {}", addr, e))?;
+                // tokio requires it to be non-blocking
+                bound.set_nonblocking(true)?;
+                *addr = bound.local_addr()?.to_string();
+                *listener = Some(bound);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ServeOpts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let servers = [
+            ("pg", &self.pg),
+            ("flight", &self.flight),
+            ("http", &self.http),
+        ];
+        let args: Vec<_> = servers
+            .iter()
+            .filter_map(|(name, addr)| addr.as_ref().map(|addr| format!("--{} {}", name, addr)))
+            .collect();
+        write!(f, "{}", args.join(" "))
+    }
+}
+
+impl CmdExecutor for ServeOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        backend.serve(&self).await?;
+        Ok("Server stopped".to_string())
+    }
+}
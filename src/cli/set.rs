@@ -0,0 +1,48 @@
+use super::ReplResult;
+use crate::{rc::save_setting, Backend, CmdExecutor, ReplContext, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct SetOpts {
+    #[arg(help = "The name of the option, e.g. `timing` or `target_partitions`")]
+    pub name: String,
+    #[arg(help = "The value of the option, e.g. `on` or `off`")]
+    pub value: String,
+    #[arg(long, help = "Also save the option to the rc file")]
+    pub save: bool,
+}
+
+pub fn set(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let value = args
+        .get_one::<String>("value")
+        .expect("expect value")
+        .to_string();
+    let save = args.get_flag("save");
+
+    let (msg, rx) = ReplMsg::new(SetOpts::new(name, value, save));
+    Ok(ctx.send(msg, rx))
+}
+
+impl SetOpts {
+    pub fn new(name: String, value: String, save: bool) -> Self {
+        Self { name, value, save }
+    }
+}
+
+impl CmdExecutor for SetOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let warning = backend.set(&self.name, &self.value).await?;
+        if self.save {
+            save_setting(&self.name, &self.value)?;
+        }
+        let ret = format!("Set {} to {}", self.name, self.value);
+        Ok(match warning {
+            Some(warning) => format!("{}\nWarning: {}", ret, warning),
+            None => ret,
+        })
+    }
+}
@@ -0,0 +1,33 @@
+use super::ReplResult;
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct ShowOpts {
+    #[arg(help = "The name of the option, or `all` to show every option")]
+    pub name: String,
+}
+
+pub fn show(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+
+    let (msg, rx) = ReplMsg::new(ShowOpts::new(name));
+    Ok(ctx.send(msg, rx))
+}
+
+impl ShowOpts {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+}
+
+impl CmdExecutor for ShowOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let options = backend.show(&self.name).await?;
+        options.display(output).await
+    }
+}
@@ -26,7 +26,8 @@ impl SqlOpts {
 
 impl CmdExecutor for SqlOpts {
     async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
         let df = backend.sql(&self.query).await?;
-        df.display().await
+        df.display(output).await
     }
 }
@@ -0,0 +1,51 @@
+use super::ReplResult;
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct TailOpts {
+    #[arg(help = "The name of the dataset")]
+    pub name: String,
+
+    #[arg(short, long, help = "The number of rows to show")]
+    pub n: Option<usize>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Columns to sort by before slicing, append `:desc` for descending order"
+    )]
+    pub order_by: Vec<String>,
+}
+
+pub fn tail(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+
+    let n = args.get_one::<usize>("n").copied();
+    let order_by = args
+        .get_many::<String>("order_by")
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default();
+
+    let (msg, rx) = ReplMsg::new(TailOpts::new(name, n, order_by));
+    Ok(ctx.send(msg, rx))
+}
+
+impl TailOpts {
+    pub fn new(name: String, n: Option<usize>, order_by: Vec<String>) -> Self {
+        Self { name, n, order_by }
+    }
+}
+
+impl CmdExecutor for TailOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let df = backend
+            .tail(&self.name, self.n.unwrap_or(5), &self.order_by)
+            .await?;
+        df.display(output).await
+    }
+}
@@ -0,0 +1,41 @@
+use super::ReplResult;
+use crate::{Backend, CmdExecutor, ReplContext, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct UseOpts {
+    #[arg(value_parser = ["backend"], help = "What to switch, only `backend` for now")]
+    pub kind: String,
+    #[arg(help = "The name of the backend, e.g. `datafusion` or `polars`")]
+    pub name: String,
+}
+
+pub fn use_backend(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+
+    let (msg, rx) = ReplMsg::new(UseOpts::new(name));
+    Ok(ctx.send(msg, rx))
+}
+
+impl UseOpts {
+    pub fn new(name: String) -> Self {
+        Self {
+            kind: "backend".to_string(),
+            name,
+        }
+    }
+}
+
+impl CmdExecutor for UseOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let errors = backend.use_backend(&self.name).await?;
+        let mut ret = format!("Switched to the {} backend", self.name);
+        if !errors.is_empty() {
+            ret = format!("{}, failed to carry over:\n{}", ret, errors.join("\n"));
+        }
+        Ok(ret)
+    }
+}
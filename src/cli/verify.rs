@@ -0,0 +1,33 @@
+use super::ReplResult;
+use crate::{Backend, CmdExecutor, ReplContext, ReplDisplay, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct VerifyOpts {
+    #[arg(help = "The SQL query, run on the current backend first")]
+    pub query: String,
+}
+
+pub fn verify(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let query = args
+        .get_one::<String>("query")
+        .expect("expect query")
+        .to_string();
+
+    let (msg, rx) = ReplMsg::new(VerifyOpts::new(query));
+    Ok(ctx.send(msg, rx))
+}
+
+impl VerifyOpts {
+    pub fn new(query: String) -> Self {
+        Self { query }
+    }
+}
+
+impl CmdExecutor for VerifyOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let output = backend.output();
+        let report = backend.verify(&self.query).await?;
+        report.display(output).await
+    }
+}
@@ -0,0 +1,104 @@
+use clap::{ArgMatches, Parser};
+
+use crate::{Backend, CmdExecutor, ReplContext, ReplMsg};
+
+use super::ReplResult;
+
+#[derive(Debug, Parser)]
+pub struct LetOpts {
+    #[arg(help = "The name of the view")]
+    pub name: String,
+    #[arg(long, help = "Replace an existing dataset of the same name")]
+    pub replace: bool,
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        help = "`=` followed by the SQL query"
+    )]
+    pub query: Vec<String>,
+}
+
+pub fn view(args: ArgMatches, ctx: &mut ReplContext) -> ReplResult {
+    let name = args
+        .get_one::<String>("name")
+        .expect("expect name")
+        .to_string();
+    let query = args
+        .get_many::<String>("query")
+        .expect("expect query")
+        .cloned()
+        .collect();
+    let replace = args.get_flag("replace");
+
+    let (msg, rx) = ReplMsg::new(LetOpts::new(name, query, replace));
+    Ok(ctx.send(msg, rx))
+}
+
+impl LetOpts {
+    pub fn new(name: String, query: Vec<String>, replace: bool) -> Self {
+        Self {
+            name,
+            query,
+            replace,
+        }
+    }
+}
+
+impl CmdExecutor for LetOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let (name, sql) = parse_assignment(&self.name, &self.query)?;
+        backend.create_view(&name, &sql, self.replace).await?;
+        Ok(format!("Created view: {}", name))
+    }
+}
+
+/// Split `<name> = <sql>` (with or without spaces around `=`) into the name and the query.
+pub(crate) fn parse_assignment(name: &str, query: &[String]) -> anyhow::Result<(String, String)> {
+    let line = format!("{} {}", name, query.join(" "));
+    let (name, sql) = line
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("expect `<name> = <sql>`"))?;
+    let (name, sql) = (name.trim(), sql.trim());
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        anyhow::bail!("invalid name: {:?}", name);
+    }
+    if sql.is_empty() {
+        anyhow::bail!("missing SQL query for {}", name);
+    }
+    Ok((name.to_string(), sql.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_assignment() {
+        let (name, sql) =
+            parse_assignment("foo", &tokens("= select * from t where a = 1")).unwrap();
+        assert_eq!(name, "foo");
+        assert_eq!(sql, "select * from t where a = 1");
+
+        let (name, sql) = parse_assignment("foo=select", &tokens("1")).unwrap();
+        assert_eq!(name, "foo");
+        assert_eq!(sql, "select 1");
+
+        assert!(parse_assignment("foo", &tokens("select 1")).is_err());
+        assert!(parse_assignment("foo", &tokens("=")).is_err());
+    }
+
+    #[test]
+    fn test_replace() {
+        let args = ["let", "--replace", "v", "=", "select", "1"];
+        let opts = LetOpts::try_parse_from(args).unwrap();
+        assert!(opts.replace);
+        assert_eq!(opts.name, "v");
+        let opts = LetOpts::try_parse_from(["let", "v", "=", "select", "1"]).unwrap();
+        assert!(!opts.replace);
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use arrow::array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+use tokio::runtime::Runtime;
+
+use crate::{
+    backend::{format_batches, OutputOptions},
+    cli::ReplCommand,
+    execute, Backend, ReplReply,
+};
+
+/// Commands running in the background, started with `bg <command>`.
+#[derive(Debug, Default)]
+pub struct Jobs {
+    next_id: u64,
+    jobs: BTreeMap<u64, Job>,
+    // whether the prompt currently shows finished jobs
+    notified: bool,
+}
+
+#[derive(Debug)]
+struct Job {
+    command: String,
+    started: Instant,
+    rx: oneshot::Receiver<ReplReply>,
+    cancel: Option<oneshot::Sender<()>>,
+    // a server, which only finishes once cancelled
+    endless: bool,
+    // set once the job has finished
+    output: Option<(String, Duration)>,
+    reported: bool,
+}
+
+impl Jobs {
+    pub fn add(
+        &mut self,
+        command: String,
+        rx: oneshot::Receiver<ReplReply>,
+        cancel: oneshot::Sender<()>,
+        endless: bool,
+    ) -> u64 {
+        self.next_id += 1;
+        let job = Job {
+            command,
+            started: Instant::now(),
+            rx,
+            cancel: Some(cancel),
+            endless,
+            output: None,
+            reported: false,
+        };
+        self.jobs.insert(self.next_id, job);
+        self.next_id
+    }
+
+    pub fn list(&mut self, output: &OutputOptions) -> anyhow::Result<String> {
+        self.poll();
+        if self.jobs.is_empty() {
+            return Ok("No jobs".to_string());
+        }
+        let jobs: Vec<_> = self.jobs.iter().collect();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(UInt64Array::from_iter_values(
+                    jobs.iter().map(|(id, _)| **id),
+                )) as ArrayRef,
+            ),
+            (
+                "status",
+                Arc::new(StringArray::from_iter_values(jobs.iter().map(
+                    |(_, j)| match j.output {
+                        Some(_) => "done",
+                        None => "running",
+                    },
+                ))) as ArrayRef,
+            ),
+            (
+                "elapsed",
+                Arc::new(StringArray::from_iter_values(
+                    jobs.iter().map(|(_, j)| format!("{:.1?}", j.elapsed())),
+                )) as ArrayRef,
+            ),
+            (
+                "command",
+                Arc::new(StringArray::from_iter_values(
+                    jobs.iter().map(|(_, j)| j.command.clone()),
+                )) as ArrayRef,
+            ),
+        ])?;
+        format_batches(&[batch], output)
+    }
+
+    /// Block until a job finishes, then remove it and return its output.
+    pub fn wait(&mut self, id: u64) -> anyhow::Result<String> {
+        self.poll();
+        let job = self
+            .jobs
+            .get(&id)
+            .ok_or_else(|| anyhow::anyhow!("job not found: {}", id))?;
+        if job.endless && job.output.is_none() {
+            anyhow::bail!(
+                "job {} runs until cancelled, stop it with `cancel {}`",
+                id,
+                id
+            );
+        }
+        let mut job = self.jobs.remove(&id).expect("expect job");
+        if job.output.is_none() {
+            let output = job
+                .rx
+                .recv()
+                .map_or_else(|_| "Job lost".to_string(), |reply| reply.output);
+            job.output = Some((output, job.started.elapsed()));
+        }
+        let (output, elapsed) = job.output.unwrap_or_default();
+        Ok(format!(
+            "{}\n[{}] {} finished in {:.1?}",
+            output, id, job.command, elapsed
+        ))
+    }
+
+    pub fn cancel(&mut self, id: u64) -> anyhow::Result<()> {
+        let job = self
+            .jobs
+            .get_mut(&id)
+            .ok_or_else(|| anyhow::anyhow!("job not found: {}", id))?;
+        match job.cancel.take() {
+            Some(cancel) if job.output.is_none() => {
+                // the job may finish before it sees the signal
+                let _ = cancel.send(());
+                Ok(())
+            }
+            _ => anyhow::bail!("job {} has already finished", id),
+        }
+    }
+
+    /// The prompt to show after a command: it lists jobs finished since the last one, and is
+    /// `None` when it doesn't change.
+    pub fn prompt(&mut self, name: &str) -> Option<String> {
+        self.poll();
+        let finished: Vec<_> = self
+            .jobs
+            .iter_mut()
+            .filter(|(_, j)| j.output.is_some() && !j.reported)
+            .map(|(id, j)| {
+                j.reported = true;
+                id.to_string()
+            })
+            .collect();
+        if !finished.is_empty() {
+            self.notified = true;
+            return Some(format!("{} [done: {}]", name, finished.join(",")));
+        }
+        if self.notified {
+            self.notified = false;
+            return Some(name.to_string());
+        }
+        None
+    }
+
+    fn poll(&mut self) {
+        for job in self.jobs.values_mut().filter(|j| j.output.is_none()) {
+            match job.rx.try_recv() {
+                Ok(reply) => job.output = Some((reply.output, job.started.elapsed())),
+                Err(oneshot::TryRecvError::Disconnected) => {
+                    job.output = Some(("Job lost".to_string(), job.started.elapsed()))
+                }
+                Err(oneshot::TryRecvError::Empty) => {}
+            }
+        }
+    }
+}
+
+impl Job {
+    fn elapsed(&self) -> Duration {
+        match &self.output {
+            Some((_, elapsed)) => *elapsed,
+            None => self.started.elapsed(),
+        }
+    }
+}
+
+/// Run a command on its own thread and runtime against a snapshot of the backend, so that the
+/// backend thread can keep serving other commands.
+pub(crate) fn run_job<T: Backend + Send + 'static>(
+    mut backend: T,
+    cmd: ReplCommand,
+    tx: oneshot::Sender<ReplReply>,
+    cancel: oneshot::Receiver<()>,
+) {
+    thread::spawn(move || {
+        backend.reset_stats();
+        let output = match Runtime::new() {
+            Ok(rt) => rt.block_on(async {
+                tokio::select! {
+                    ret = execute(cmd, &mut backend) => ret.unwrap_or_else(|e| format!("Failed: {}", e).into()),
+                    Ok(()) = cancel => "Cancelled".to_string().into(),
+                }
+            }),
+            Err(e) => format!("Failed to create runtime: {}", e).into(),
+        };
+        let _ = tx.send(output);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::OutputFormat;
+
+    #[test]
+    fn test_jobs() {
+        let mut jobs = Jobs::default();
+        let (tx, rx) = oneshot::channel();
+        let (cancel, _) = oneshot::channel();
+        let id = jobs.add("sql 'select 1'".to_string(), rx, cancel, false);
+        assert_eq!(jobs.prompt("taotie"), None);
+
+        tx.send("done".to_string().into()).unwrap();
+        assert_eq!(jobs.prompt("taotie"), Some("taotie [done: 1]".to_string()));
+        assert_eq!(jobs.prompt("taotie"), Some("taotie".to_string()));
+        assert_eq!(jobs.prompt("taotie"), None);
+
+        assert!(jobs.cancel(id).is_err());
+        let output = OutputOptions {
+            format: OutputFormat::Csv,
+            max_rows: None,
+        };
+        let list = jobs.list(&output).unwrap();
+        assert!(
+            list.starts_with("id,status,elapsed,command\n1,done,"),
+            "{}",
+            list
+        );
+        assert!(jobs
+            .wait(id)
+            .unwrap()
+            .starts_with("done\n[1] sql 'select 1' finished in"));
+        assert!(jobs.wait(id).is_err());
+    }
+
+    #[test]
+    fn test_wait_endless() {
+        let mut jobs = Jobs::default();
+        let (_tx, rx) = oneshot::channel();
+        let (cancel, _) = oneshot::channel();
+        let id = jobs.add("serve --port 0".to_string(), rx, cancel, true);
+        let err = jobs.wait(id).unwrap_err().to_string();
+        assert!(err.contains("runs until cancelled"), "{}", err);
+        // the job is still there to cancel
+        assert!(jobs.cancel(id).is_ok());
+    }
+}
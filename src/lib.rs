@@ -1,14 +1,29 @@
-use std::thread;
+use std::{cell::Cell, ops::Range, thread, time::Instant};
 
-use backend::DataFusionBackend;
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
+use backend::{CheckReport, CommandStats, DatasetSource, OutputOptions, Rule, VerifyReport};
+use clap::CommandFactory;
 use cli::*;
 use crossbeam_channel as mpsc;
 use enum_dispatch::enum_dispatch;
 use reedline_repl_rs::CallBackMap;
+use registry::create_backend;
 use tokio::runtime::Runtime;
 
+pub use jobs::Jobs;
+pub use rc::{rc_file, run_rc};
+pub use session::{ConvertOptions, Taotie};
+
 pub mod backend;
 pub mod cli;
+mod jobs;
+mod queries;
+mod rc;
+mod registry;
+mod session;
+#[cfg(test)]
+mod test_utils;
+mod utils;
 
 #[enum_dispatch]
 trait CmdExecutor {
@@ -18,38 +33,158 @@ trait CmdExecutor {
 trait Backend {
     type DataFrame: ReplDisplay;
     async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()>;
-    async fn list(&self) -> anyhow::Result<impl ReplDisplay>;
-    async fn schema(&self, name: &str) -> anyhow::Result<impl ReplDisplay>;
-    async fn describe(&self, name: &str) -> anyhow::Result<impl ReplDisplay>;
+    async fn list(&self, exact: bool) -> anyhow::Result<impl ReplDisplay>;
+    async fn schema(&self, name: &str) -> anyhow::Result<SchemaRef>;
+    async fn describe(&self, name: &str, sparkline: bool) -> anyhow::Result<impl ReplDisplay>;
     async fn head(&self, name: &str, size: usize) -> anyhow::Result<impl ReplDisplay>;
+    async fn tail(
+        &self,
+        name: &str,
+        size: usize,
+        order_by: &[String],
+    ) -> anyhow::Result<impl ReplDisplay>;
+    async fn rows(
+        &self,
+        name: &str,
+        range: Range<usize>,
+        order_by: &[String],
+    ) -> anyhow::Result<impl ReplDisplay>;
     async fn sql(&self, sql: &str) -> anyhow::Result<impl ReplDisplay>;
+    // the results of a query as a single batch, to compare them across backends
+    async fn collect(&self, sql: &str) -> anyhow::Result<RecordBatch>;
+    async fn explain(&self, sql: &str, analyze: bool) -> anyhow::Result<impl ReplDisplay>;
+    // both fail on the name of an existing dataset unless `replace`
+    async fn create_view(&mut self, name: &str, sql: &str, replace: bool) -> anyhow::Result<()>;
+    async fn cache(&mut self, name: &str, sql: &str, replace: bool) -> anyhow::Result<usize>;
+    async fn deregister(&mut self, name: &str) -> anyhow::Result<()>;
+    async fn inspect(&self, target: &str) -> anyhow::Result<impl ReplDisplay>;
+    async fn convert(&self, opts: &ConvertOpts) -> anyhow::Result<u64>;
+    async fn diff(&mut self, opts: &DiffOpts) -> anyhow::Result<impl ReplDisplay>;
+    async fn dups(&mut self, opts: &DupsOpts) -> anyhow::Result<impl ReplDisplay>;
+    async fn counts(&self, opts: &CountsOpts) -> anyhow::Result<impl ReplDisplay>;
+    async fn hist(&self, name: &str, column: &str, bins: usize)
+        -> anyhow::Result<impl ReplDisplay>;
+    async fn corr(&self, opts: &CorrOpts) -> anyhow::Result<impl ReplDisplay>;
+    async fn check(&self, name: &str, rules: &[Rule]) -> anyhow::Result<CheckReport>;
+    async fn sample(&mut self, opts: &SampleOpts) -> anyhow::Result<impl ReplDisplay>;
+    // returns a warning when the backend accepts the setting but ignores it
+    async fn set(&mut self, name: &str, value: &str) -> anyhow::Result<Option<String>>;
+    // how results are rendered, set with `set format` and `set max_rows`
+    fn output(&self) -> OutputOptions;
+    // the settings changed with `set`, to apply them again on another backend
+    fn changed_settings(&self) -> Vec<(String, String)>;
+    async fn show(&self, name: &str) -> anyhow::Result<impl ReplDisplay>;
+    fn reset_stats(&mut self);
+    // `None` unless timing is on
+    fn stats(&self) -> Option<CommandStats>;
+    // runs until the process stops or the job is cancelled
+    async fn serve(&self, opts: &ServeOpts) -> anyhow::Result<()>;
+    // a copy of the backend for a background job, with its own catalog
+    async fn snapshot(&self) -> anyhow::Result<Self>
+    where
+        Self: Sized;
+    fn name(&self) -> &'static str;
+    // the registered datasets, to register them again on another backend
+    fn catalog(&self) -> Vec<(String, DatasetSource)>;
+    // switch to another backend, returning the datasets and settings it failed to carry over
+    async fn use_backend(&mut self, _name: &str) -> anyhow::Result<Vec<String>> {
+        anyhow::bail!("the {} backend can't be switched", self.name())
+    }
+    // run a query on every backend and compare the results with those of this one
+    async fn verify(&self, _sql: &str) -> anyhow::Result<VerifyReport> {
+        anyhow::bail!("the {} backend can't verify queries", self.name())
+    }
 }
 
 trait ReplDisplay {
-    async fn display(self) -> anyhow::Result<String>;
+    async fn display(self, output: OutputOptions) -> anyhow::Result<String>;
 }
 
 pub struct ReplContext {
     pub tx: mpsc::Sender<ReplMsg>,
+    pub jobs: Jobs,
+    // those of the backend after its last reply, for the output of the REPL itself, e.g. `jobs`
+    output: Cell<OutputOptions>,
 }
 
 pub struct ReplMsg {
     cmd: ReplCommand,
-    tx: oneshot::Sender<String>,
+    tx: oneshot::Sender<ReplReply>,
+    // only for background jobs
+    cancel: Option<oneshot::Receiver<()>>,
+}
+
+/// The reply of the backend to a command.
+#[derive(Debug)]
+pub struct ReplReply {
+    pub output: String,
+    // false when the command ran but found problems, i.e. failing rules of `check`
+    pub passed: bool,
+    // the output options of the backend once the command ran, none for background jobs
+    pub output_options: Option<OutputOptions>,
 }
 
 pub type ReplCallBacks = CallBackMap<ReplContext, reedline_repl_rs::Error>;
 
+// each command also runs in the background when its line ends with `&`
+macro_rules! callbacks {
+    ($($name:literal => $callback:path),* $(,)?) => {{
+        let mut callbacks = ReplCallBacks::new();
+        $(callbacks.insert($name.to_string(), |args, ctx| {
+            cli::run_or_spawn($name, args, ctx, $callback)
+        });)*
+        callbacks
+    }};
+}
+
 pub fn get_callbacks() -> ReplCallBacks {
-    let mut callbacks = ReplCallBacks::new();
-    callbacks.insert("connect".to_string(), cli::connect);
-    callbacks.insert("list".to_string(), cli::list);
-    callbacks.insert("schema".to_string(), cli::schema);
-    callbacks.insert("describe".to_string(), cli::describe);
-    callbacks.insert("head".to_string(), cli::head);
-    callbacks.insert("sql".to_string(), cli::sql);
-    callbacks.insert("exit".to_string(), exit);
-    callbacks
+    callbacks! {
+        "connect" => cli::connect,
+        "list" => cli::list,
+        "schema" => cli::schema,
+        "describe" => cli::describe,
+        "head" => cli::head,
+        "tail" => cli::tail,
+        "rows" => cli::rows,
+        "sql" => cli::sql,
+        "explain" => cli::explain,
+        "let" => cli::view,
+        "cache" => cli::cache,
+        "drop" => cli::drop_dataset,
+        "inspect" => cli::inspect,
+        "convert" => cli::convert,
+        "schema-diff" => cli::schema_diff,
+        "diff" => cli::diff,
+        "dups" => cli::dups,
+        "counts" => cli::counts,
+        "hist" => cli::hist,
+        "corr" => cli::corr,
+        "sample" => cli::sample,
+        "check" => cli::check,
+        "verify" => cli::verify,
+        "set" => cli::set,
+        "show" => cli::show,
+        "serve" => cli::serve,
+        "bg" => cli::bg,
+        "jobs" => cli::jobs,
+        "wait" => cli::wait,
+        "cancel" => cli::cancel,
+        "use" => cli::use_backend,
+        "query" => cli::query,
+        "exit" => exit,
+    }
+}
+
+/// The commands of the REPL, each taking a trailing `&`.
+pub fn repl_commands() -> clap::Command {
+    let cmd = ReplCommand::command();
+    let names: Vec<_> = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    names.iter().fold(cmd, |cmd, name| {
+        cmd.mut_subcommand(name, cli::with_background)
+    })
 }
 
 impl ReplContext {
@@ -57,32 +192,99 @@ impl ReplContext {
         let (tx, rx) = mpsc::unbounded::<ReplMsg>();
         let rt = Runtime::new().expect("Failed to create runtime");
 
-        let mut backend = DataFusionBackend::new();
+        let mut backend = create_backend("datafusion").expect("expect the default backend");
         thread::Builder::new()
             .name("ReplBackend".to_string())
             .spawn(move || {
-                while let Ok(msg) = rx.recv() {
-                    if let Err(e) = rt.block_on(async {
-                        let ret = msg.cmd.execute(&mut backend).await?;
-                        msg.tx.send(ret)?;
-                        Ok::<_, anyhow::Error>(())
-                    }) {
-                        eprintln!("Failed to process command: {}", e);
+                while let Ok(ReplMsg { cmd, tx, cancel }) = rx.recv() {
+                    if let Some(cancel) = cancel {
+                        match rt.block_on(backend.snapshot()) {
+                            Ok(snapshot) => jobs::run_job(snapshot, cmd, tx, cancel),
+                            Err(e) => {
+                                let _ = tx.send(format!("Failed to start job: {}", e).into());
+                            }
+                        }
+                        continue;
+                    }
+                    backend.reset_stats();
+                    let start = Instant::now();
+                    let ret = rt.block_on(async {
+                        let mut ret = execute(cmd, &mut backend).await?;
+                        if let Some(stats) = backend.stats() {
+                            ret.output =
+                                format!("{}\n{}", ret.output, stats.summary(start.elapsed()));
+                        }
+                        Ok::<_, anyhow::Error>(ret)
+                    });
+                    // print the error before dropping `tx`, on which `taotie <command>` exits
+                    match ret {
+                        Ok(mut ret) => {
+                            ret.output_options = Some(backend.output());
+                            let _ = tx.send(ret);
+                        }
+                        Err(e) => eprintln!("Failed to process command: {}", e),
                     }
                 }
             })
             .unwrap();
 
-        Self { tx }
+        Self {
+            tx,
+            jobs: Jobs::default(),
+            output: Cell::default(),
+        }
     }
 
-    pub fn send(&self, msg: ReplMsg, rx: oneshot::Receiver<String>) -> Option<String> {
+    pub fn send(&self, msg: ReplMsg, rx: oneshot::Receiver<ReplReply>) -> Option<String> {
+        self.reply(msg, rx).map(|reply| reply.output)
+    }
+
+    /// Like `send`, but also tells whether the command passed, e.g. for the exit code.
+    pub fn reply(&self, msg: ReplMsg, rx: oneshot::Receiver<ReplReply>) -> Option<ReplReply> {
         // 发送消息到后端开始处理
         if let Err(e) = self.tx.send(msg) {
             eprintln!("Repl Send Error: {}", e);
             std::process::exit(1);
         }
-        rx.recv().ok()
+        let reply = rx.recv().ok()?;
+        if let Some(options) = reply.output_options {
+            self.output.set(options);
+        }
+        Some(reply)
+    }
+
+    /// The output options of the backend, as of its last reply.
+    pub fn output(&self) -> OutputOptions {
+        self.output.get()
+    }
+
+    /// Start a command as a background job and return its id.
+    pub fn spawn_job(&mut self, command: String, cmd: ReplCommand) -> u64 {
+        let endless = matches!(cmd, ReplCommand::Serve(_));
+        let (msg, rx, cancel) = ReplMsg::job(cmd);
+        if let Err(e) = self.tx.send(msg) {
+            eprintln!("Repl Send Error: {}", e);
+            std::process::exit(1);
+        }
+        self.jobs.add(command, rx, cancel, endless)
+    }
+}
+
+impl From<String> for ReplReply {
+    fn from(output: String) -> Self {
+        Self {
+            output,
+            passed: true,
+            output_options: None,
+        }
+    }
+}
+
+// `check` is the only command whose reply tells more than its output
+async fn execute<T: Backend>(cmd: ReplCommand, backend: &mut T) -> anyhow::Result<ReplReply> {
+    match cmd {
+        ReplCommand::Check(opts) => opts.check(backend).await,
+        cmd => Ok(cmd.execute(backend).await?.into()),
     }
 }
 
@@ -93,14 +295,30 @@ impl Default for ReplContext {
 }
 
 impl ReplMsg {
-    pub fn new(cmd: impl Into<ReplCommand>) -> (Self, oneshot::Receiver<String>) {
+    pub fn new(cmd: impl Into<ReplCommand>) -> (Self, oneshot::Receiver<ReplReply>) {
         let (tx, rx) = oneshot::channel();
         (
             Self {
                 cmd: cmd.into(),
                 tx,
+                cancel: None,
+            },
+            rx,
+        )
+    }
+
+    // also returns the sender to cancel the job
+    fn job(cmd: ReplCommand) -> (Self, oneshot::Receiver<ReplReply>, oneshot::Sender<()>) {
+        let (tx, rx) = oneshot::channel();
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        (
+            Self {
+                cmd,
+                tx,
+                cancel: Some(cancel_rx),
             },
             rx,
+            cancel_tx,
         )
     }
 }
@@ -1,22 +1,133 @@
 use anyhow::Result;
+use clap::{Parser, Subcommand};
 use reedline_repl_rs::Repl;
-use taotie::{cli::ReplCommand, get_callbacks, ReplContext};
+use taotie::{
+    cli::{CheckOpts, ConvertOpts, ServeOpts, SetOpts, UseOpts},
+    get_callbacks, repl_commands, run_rc, ReplContext, ReplMsg,
+};
 
 const HISTORY_SIZE: usize = 1024;
+const PROMPT: &str = "taotie";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "taotie",
+    version,
+    about = "Taotie, your dataset exploration REPL"
+)]
+struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "The backend to start with, `datafusion` by default or `polars`"
+    )]
+    backend: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "The max memory of queries, e.g. 4GB, beyond which sorts and joins spill to disk"
+    )]
+    memory_limit: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "The directory of spill files, defaults to the OS temp dir"
+    )]
+    spill_dir: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(about = "Convert a dataset to another format")]
+    Convert(ConvertOpts),
+    #[command(about = "Check a dataset against data quality rules, exit non-zero on failure")]
+    Check(CheckOpts),
+    #[command(about = "Serve the datasets registered in the rc file until stopped")]
+    Serve(ServeOpts),
+}
 
 fn main() -> Result<()> {
+    let cli = Cli::parse();
     let ctx = ReplContext::new();
+    // switch before the rc file registers any dataset
+    if let Some(name) = cli.backend {
+        let (msg, rx) = ReplMsg::new(UseOpts::new(name));
+        if ctx.send(msg, rx).is_none() {
+            std::process::exit(1);
+        }
+    }
+    run_rc(&ctx)?;
+    // the command line takes precedence over the rc file
+    let settings = [
+        ("memory_limit", cli.memory_limit),
+        ("spill_dir", cli.spill_dir),
+    ];
+    for (name, value) in settings {
+        if let Some(value) = value {
+            let (msg, rx) = ReplMsg::new(SetOpts::new(name.to_string(), value, false));
+            match ctx.send(msg, rx) {
+                // only the warning of a setting the backend ignores, e.g. on polars
+                Some(output) => output
+                    .lines()
+                    .filter(|line| line.starts_with("Warning:"))
+                    .for_each(|line| eprintln!("{}", line)),
+                None => std::process::exit(1),
+            }
+        }
+    }
+
+    match cli.command {
+        Some(cmd) => run(ctx, cmd),
+        None => run_repl(ctx),
+    }
+}
+
+fn run(ctx: ReplContext, cmd: Command) -> Result<()> {
+    let (msg, rx) = match cmd {
+        Command::Convert(opts) => ReplMsg::new(opts),
+        Command::Check(opts) => ReplMsg::new(opts),
+        Command::Serve(mut opts) => {
+            opts.bind()?;
+            println!("Serving {}", opts);
+            ReplMsg::new(opts)
+        }
+    };
+    let Some(reply) = ctx.reply(msg, rx) else {
+        std::process::exit(1);
+    };
+    println!("{}", reply.output);
+    // failing checks are reported as usual, but exit non-zero for scripts and CI
+    if !reply.passed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_repl(ctx: ReplContext) -> Result<()> {
     let callbacks = get_callbacks();
 
     let history_file = dirs::home_dir()
         .expect("expect home dir")
         .join(".taotie_history");
     let mut repl = Repl::new(ctx)
+        .with_name(PROMPT)
         .with_history(history_file, HISTORY_SIZE)
         .with_banner("Welcome to Taotie, your dataset exploration REPL!")
-        .with_derived::<ReplCommand>(callbacks);
+        .with_on_after_command(update_prompt);
+    for cmd in repl_commands().get_subcommands() {
+        if let Some(callback) = callbacks.get(cmd.get_name()) {
+            repl = repl.with_command(cmd.clone(), *callback);
+        }
+    }
 
     repl.run()?;
 
     Ok(())
 }
+
+// show the background jobs which finished since the last command
+fn update_prompt(ctx: &mut ReplContext) -> Result<Option<String>, reedline_repl_rs::Error> {
+    Ok(ctx.jobs.prompt(PROMPT))
+}
@@ -0,0 +1,316 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use arrow::{
+    array::{ArrayRef, RecordBatch, StringArray},
+    util::pretty::pretty_format_batches,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::utils::quote_literal;
+
+/// The file of saved queries, e.g. `~/.config/taotie/queries.yaml` on Linux.
+pub fn queries_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("taotie").join("queries.yaml"))
+}
+
+/// Named queries with `$name` parameters, saved with `query save` and run with `query run`.
+#[derive(Debug, Default)]
+pub struct SavedQueries(BTreeMap<String, String>);
+
+impl SavedQueries {
+    pub fn load() -> anyhow::Result<Self> {
+        match queries_file().filter(|p| p.exists()) {
+            Some(path) => Ok(Self(serde_yaml::from_str(&fs::read_to_string(path)?)?)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = queries_file().ok_or_else(|| anyhow::anyhow!("no config directory found"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_yaml::to_string(&self.0)?)?;
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|name| name.as_str())
+    }
+
+    pub fn get(&self, name: &str) -> anyhow::Result<&str> {
+        self.0
+            .get(name)
+            .map(|sql| sql.as_str())
+            .ok_or_else(|| anyhow::anyhow!("saved query not found: {}", name))
+    }
+
+    /// Save a query, checking its parameters are well formed, and return whether it replaced one.
+    pub fn insert(&mut self, name: &str, sql: &str) -> anyhow::Result<bool> {
+        parameters(sql)?;
+        Ok(self.0.insert(name.to_string(), sql.to_string()).is_some())
+    }
+
+    pub fn list(&self) -> anyhow::Result<String> {
+        if self.0.is_empty() {
+            return Ok("No saved queries".to_string());
+        }
+        let params: Vec<_> = self
+            .0
+            .values()
+            .map(|sql| parameters(sql).map(|p| p.join(", ")).unwrap_or_default())
+            .collect();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "name",
+                Arc::new(StringArray::from_iter_values(self.0.keys())) as ArrayRef,
+            ),
+            (
+                "parameters",
+                Arc::new(StringArray::from_iter_values(params)) as ArrayRef,
+            ),
+            (
+                "sql",
+                Arc::new(StringArray::from_iter_values(self.0.values())) as ArrayRef,
+            ),
+        ])?;
+        Ok(pretty_format_batches(&[batch])?.to_string())
+    }
+}
+
+/// Substitute the `$name` parameters of a query with typed literals, e.g. `DATE '2024-01-01'`
+/// for `start=2024-01-01`, or with the type given as `name:type`, e.g. `code:str=007`.
+/// Every parameter must be given, and only those of the query.
+pub fn bind(sql: &str, params: &[(String, String)]) -> anyhow::Result<String> {
+    let values: BTreeMap<_, _> = params
+        .iter()
+        .map(|(k, v)| match k.split_once(':') {
+            Some((name, kind)) => (name, (Some(kind), v.as_str())),
+            None => (k.as_str(), (None, v.as_str())),
+        })
+        .collect();
+    let mut used = BTreeSet::new();
+    let sql = substitute(sql, |name| match values.get(name) {
+        Some((kind, value)) => {
+            used.insert(name.to_string());
+            match kind {
+                Some(kind) => typed_literal(kind, value),
+                None => Ok(literal(value)),
+            }
+        }
+        None => anyhow::bail!("missing parameter: {}", name),
+    })?;
+    if let Some(name) = values.keys().find(|name| !used.contains(**name)) {
+        anyhow::bail!("unknown parameter: {}", name);
+    }
+    Ok(sql)
+}
+
+/// The names of the parameters of a query, in order of first use.
+pub fn parameters(sql: &str) -> anyhow::Result<Vec<String>> {
+    let mut names: Vec<String> = vec![];
+    substitute(sql, |name| {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        Ok(String::new())
+    })?;
+    Ok(names)
+}
+
+// replace each `$name` outside of quotes and comments with the result of `f`
+fn substitute(
+    sql: &str,
+    mut f: impl FnMut(&str) -> anyhow::Result<String>,
+) -> anyhow::Result<String> {
+    let mut output = String::with_capacity(sql.len());
+    let mut quote = None;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '-') if chars.peek().is_some_and(|(_, c)| *c == '-') => {
+                let end = sql[i..].find('\n').map_or(sql.len(), |n| i + n);
+                output.push_str(&sql[i..end]);
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+                continue;
+            }
+            (None, '/') if chars.peek().is_some_and(|(_, c)| *c == '*') => {
+                let Some(n) = sql[i + 2..].find("*/") else {
+                    anyhow::bail!("unterminated comment in query");
+                };
+                let end = i + 2 + n + 2;
+                output.push_str(&sql[i..end]);
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+                continue;
+            }
+            (None, '$')
+                if chars
+                    .peek()
+                    .is_some_and(|(_, c)| c.is_alphabetic() || *c == '_') =>
+            {
+                let mut end = sql.len();
+                while let Some(&(j, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        end = j;
+                        break;
+                    }
+                    chars.next();
+                }
+                output.push_str(&f(&sql[i + 1..end])?);
+                continue;
+            }
+            _ => {}
+        }
+        output.push(c);
+    }
+    if quote.is_some() {
+        anyhow::bail!("unterminated quote in query");
+    }
+    Ok(output)
+}
+
+// numbers and booleans as they are, dates and timestamps typed, anything else as a string,
+// including a quoted value without its quotes
+fn literal(value: &str) -> String {
+    for q in ['\'', '"'] {
+        if let Some(value) = value
+            .strip_prefix(q)
+            .and_then(|value| value.strip_suffix(q))
+        {
+            return quote_literal(value);
+        }
+    }
+    if value.parse::<i64>().is_ok() || value.parse::<f64>().is_ok_and(|v| v.is_finite()) {
+        // so that `x -$n` can't become a comment
+        return match value.starts_with('-') {
+            true => format!("({})", value),
+            false => value.to_string(),
+        };
+    }
+    if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+        return value.to_uppercase();
+    }
+    if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() {
+        return format!("DATE {}", quote_literal(value));
+    }
+    let timestamp = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .any(|fmt| NaiveDateTime::parse_from_str(value, fmt).is_ok());
+    if timestamp {
+        return format!("TIMESTAMP {}", quote_literal(value));
+    }
+    quote_literal(value)
+}
+
+// the literal of a value of the type given with the parameter, e.g. `str` for a leading zero
+fn typed_literal(kind: &str, value: &str) -> anyhow::Result<String> {
+    let invalid = || anyhow::anyhow!("invalid {} value: {}", kind, value);
+    let literal = match kind {
+        "str" => quote_literal(value),
+        "int" => value.parse::<i64>().map_err(|_| invalid())?.to_string(),
+        "float" => match value.parse::<f64>() {
+            Ok(v) if v.is_finite() => v.to_string(),
+            _ => return Err(invalid()),
+        },
+        "bool"
+            if ["true", "false"]
+                .iter()
+                .any(|b| value.eq_ignore_ascii_case(b)) =>
+        {
+            value.to_uppercase()
+        }
+        "bool" => return Err(invalid()),
+        "date" => {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid())?;
+            format!("DATE {}", quote_literal(value))
+        }
+        "timestamp" => format!("TIMESTAMP {}", quote_literal(value)),
+        _ => anyhow::bail!(
+            "unknown parameter type: {}, expect str, int, float, bool, date or timestamp",
+            kind
+        ),
+    };
+    // so that `x -$n` can't become a comment
+    match literal.starts_with('-') {
+        true => Ok(format!("({})", literal)),
+        false => Ok(literal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind() -> anyhow::Result<()> {
+        let sql = "select * from sales where day >= $start and day < $end and region = $region and note != '$start'";
+        let params = |v: &[(&str, &str)]| -> Vec<(String, String)> {
+            v.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert_eq!(parameters(sql)?, vec!["start", "end", "region"]);
+        assert_eq!(
+            bind(
+                sql,
+                &params(&[
+                    ("start", "2024-01-01"),
+                    ("end", "2024-02-01 00:00:00"),
+                    ("region", "it's")
+                ])
+            )?,
+            "select * from sales where day >= DATE '2024-01-01' and day < TIMESTAMP '2024-02-01 00:00:00' and region = 'it''s' and note != '$start'"
+        );
+        assert_eq!(
+            bind(
+                "select $n, 1 -$m, $flag",
+                &params(&[("n", "1.5"), ("m", "-1"), ("flag", "true")])
+            )?,
+            "select 1.5, 1 -(-1), TRUE"
+        );
+        assert_eq!(
+            bind(
+                "select $code, $id, $day, $name",
+                &params(&[
+                    ("code:str", "007"),
+                    ("id:int", "-7"),
+                    ("day:date", "2024-01-01"),
+                    ("name", "'42'")
+                ])
+            )?,
+            "select '007', (-7), DATE '2024-01-01', '42'"
+        );
+        assert!(bind("select $n", &params(&[("n:int", "seven")])).is_err());
+        assert!(bind("select $n", &params(&[("n:text", "seven")])).is_err());
+        assert!(bind(sql, &params(&[("start", "2024-01-01")])).is_err());
+        assert!(bind("select 1", &params(&[("start", "2024-01-01")])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_comments() -> anyhow::Result<()> {
+        let sql = "select $a -- or $b\nfrom t /* where x = $c */ where y = $d";
+        assert_eq!(parameters(sql)?, vec!["a", "d"]);
+        assert_eq!(
+            bind(
+                sql,
+                &[
+                    ("a".to_string(), "1".to_string()),
+                    ("d".to_string(), "x".to_string())
+                ]
+            )?,
+            "select 1 -- or $b\nfrom t /* where x = $c */ where y = 'x'"
+        );
+        // quotes in comments don't open strings
+        assert_eq!(parameters("select $a -- it's\n, $b")?, vec!["a", "b"]);
+        assert!(parameters("select $a /* open").is_err());
+        Ok(())
+    }
+}
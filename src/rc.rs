@@ -0,0 +1,105 @@
+use std::{fs, iter, path::PathBuf};
+
+use clap::Parser;
+
+use crate::{cli::ReplCommand, ReplContext, ReplMsg};
+
+/// The rc file, whose commands run at startup, e.g. `~/.config/taotie/taotierc` on Linux.
+pub fn rc_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("taotie").join("taotierc"))
+}
+
+/// Run the commands in the rc file, one per line, skipping blank lines and `#` comments.
+pub fn run_rc(ctx: &ReplContext) -> anyhow::Result<()> {
+    let Some(path) = rc_file().filter(|p| p.exists()) else {
+        return Ok(());
+    };
+    let content = fs::read_to_string(&path)?;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_command(line) {
+            Ok(cmd) => {
+                let (msg, rx) = ReplMsg::new(cmd);
+                ctx.send(msg, rx);
+            }
+            Err(e) => eprintln!("{}:{}: invalid command: {}", path.display(), i + 1, e),
+        }
+    }
+    Ok(())
+}
+
+// the words are quoted like in a shell, e.g. `sql "select * from t"`
+fn parse_command(line: &str) -> anyhow::Result<ReplCommand> {
+    let words = shlex::split(line).ok_or_else(|| anyhow::anyhow!("unbalanced quotes"))?;
+    Ok(ReplCommand::try_parse_from(
+        iter::once("taotie".to_string()).chain(words),
+    )?)
+}
+
+/// Save `set <name> <value>` to the rc file, replacing a previous value of the option.
+pub(crate) fn save_setting(name: &str, value: &str) -> anyhow::Result<()> {
+    let path = rc_file().ok_or_else(|| anyhow::anyhow!("no config directory found"))?;
+    let content = match path.exists() {
+        true => fs::read_to_string(&path)?,
+        false => String::new(),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // read back like the other lines, e.g. a spill dir with spaces
+    let value = shlex::try_quote(value)?;
+    fs::write(&path, update_setting(&content, name, &value))?;
+    Ok(())
+}
+
+fn update_setting(content: &str, name: &str, value: &str) -> String {
+    let setting = format!("set {} {}", name, value);
+    let mut lines = vec![];
+    let mut replaced = false;
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+        if words.next() == Some("set") && words.next() == Some(name) {
+            if !replaced {
+                lines.push(setting.clone());
+                replaced = true;
+            }
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    if !replaced {
+        lines.push(setting);
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        let cmd = parse_command(r#"sql "select * from t where name = 'a b'""#).unwrap();
+        match cmd {
+            ReplCommand::Sql(opts) => assert_eq!(opts.query, "select * from t where name = 'a b'"),
+            cmd => panic!("unexpected command: {:?}", cmd),
+        }
+        assert!(parse_command(r#"sql "select 1"#).is_err());
+    }
+
+    #[test]
+    fn test_update_setting() {
+        let content = "# taotie\nset timing on\nset format csv\n";
+        assert_eq!(
+            update_setting(content, "timing", "off"),
+            "# taotie\nset timing off\nset format csv\n"
+        );
+        assert_eq!(
+            update_setting("", "batch_size", "1024"),
+            "set batch_size 1024\n"
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use std::ops::Range;
+
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
+
+use crate::{
+    backend::{
+        CheckReport, CommandStats, DataFusionBackend, DatasetSource, OutputOptions, PolarsBackend,
+        Rule, VerifyReport,
+    },
+    cli::{
+        ConnectOpts, ConvertOpts, CorrOpts, CountsOpts, DiffOpts, DupsOpts, SampleOpts, ServeOpts,
+    },
+    Backend, ReplDisplay,
+};
+
+// the backends to choose from with `--backend` or `use backend <name>`, the first is the default
+const BACKENDS: &[&str] = &["datafusion", "polars"];
+
+pub(crate) fn backend_names() -> Vec<&'static str> {
+    BACKENDS.to_vec()
+}
+
+pub(crate) fn create_backend(name: &str) -> anyhow::Result<AnyBackend> {
+    match name {
+        "datafusion" => Ok(AnyBackend::DataFusion(DataFusionBackend::new())),
+        "polars" => Ok(AnyBackend::Polars(Box::default())),
+        _ => anyhow::bail!(
+            "unknown backend: {}, expect one of {}",
+            name,
+            backend_names().join(", ")
+        ),
+    }
+}
+
+/// The backend the REPL runs, which can be switched at runtime. Results are displayed before
+/// they are returned, as each backend has its own types.
+pub(crate) enum AnyBackend {
+    DataFusion(DataFusionBackend),
+    // boxed as it is much larger
+    Polars(Box<PolarsBackend>),
+}
+
+// forward a call to the current backend
+macro_rules! dispatch {
+    ($self:ident, $backend:ident => $call:expr) => {
+        match $self {
+            AnyBackend::DataFusion($backend) => $call,
+            AnyBackend::Polars($backend) => $call,
+        }
+    };
+}
+
+// forward a call to the current backend and display its result with its output options
+macro_rules! display {
+    ($self:ident, $backend:ident => $call:expr) => {
+        dispatch!($self, $backend => {
+            let output = $backend.output();
+            $call.await?.display(output).await
+        })
+    };
+}
+
+impl Backend for AnyBackend {
+    type DataFrame = String;
+
+    async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()> {
+        dispatch!(self, b => b.connect(opts).await)
+    }
+
+    async fn list(&self, exact: bool) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.list(exact))
+    }
+
+    async fn schema(&self, name: &str) -> anyhow::Result<SchemaRef> {
+        dispatch!(self, b => b.schema(name).await)
+    }
+
+    async fn describe(&self, name: &str, sparkline: bool) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.describe(name, sparkline))
+    }
+
+    async fn head(&self, name: &str, size: usize) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.head(name, size))
+    }
+
+    async fn tail(
+        &self,
+        name: &str,
+        size: usize,
+        order_by: &[String],
+    ) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.tail(name, size, order_by))
+    }
+
+    async fn rows(
+        &self,
+        name: &str,
+        range: Range<usize>,
+        order_by: &[String],
+    ) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.rows(name, range, order_by))
+    }
+
+    async fn sql(&self, sql: &str) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.sql(sql))
+    }
+
+    async fn collect(&self, sql: &str) -> anyhow::Result<RecordBatch> {
+        dispatch!(self, b => b.collect(sql).await)
+    }
+
+    async fn explain(&self, sql: &str, analyze: bool) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.explain(sql, analyze))
+    }
+
+    async fn create_view(&mut self, name: &str, sql: &str, replace: bool) -> anyhow::Result<()> {
+        dispatch!(self, b => b.create_view(name, sql, replace).await)
+    }
+
+    async fn cache(&mut self, name: &str, sql: &str, replace: bool) -> anyhow::Result<usize> {
+        dispatch!(self, b => b.cache(name, sql, replace).await)
+    }
+
+    async fn deregister(&mut self, name: &str) -> anyhow::Result<()> {
+        dispatch!(self, b => b.deregister(name).await)
+    }
+
+    async fn inspect(&self, target: &str) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.inspect(target))
+    }
+
+    async fn convert(&self, opts: &ConvertOpts) -> anyhow::Result<u64> {
+        dispatch!(self, b => b.convert(opts).await)
+    }
+
+    async fn diff(&mut self, opts: &DiffOpts) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.diff(opts))
+    }
+
+    async fn dups(&mut self, opts: &DupsOpts) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.dups(opts))
+    }
+
+    async fn counts(&self, opts: &CountsOpts) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.counts(opts))
+    }
+
+    async fn hist(
+        &self,
+        name: &str,
+        column: &str,
+        bins: usize,
+    ) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.hist(name, column, bins))
+    }
+
+    async fn corr(&self, opts: &CorrOpts) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.corr(opts))
+    }
+
+    async fn check(&self, name: &str, rules: &[Rule]) -> anyhow::Result<CheckReport> {
+        dispatch!(self, b => b.check(name, rules).await)
+    }
+
+    async fn sample(&mut self, opts: &SampleOpts) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.sample(opts))
+    }
+
+    async fn set(&mut self, name: &str, value: &str) -> anyhow::Result<Option<String>> {
+        dispatch!(self, b => b.set(name, value).await)
+    }
+
+    fn output(&self) -> OutputOptions {
+        dispatch!(self, b => b.output())
+    }
+
+    fn changed_settings(&self) -> Vec<(String, String)> {
+        dispatch!(self, b => b.changed_settings())
+    }
+
+    async fn show(&self, name: &str) -> anyhow::Result<impl ReplDisplay> {
+        display!(self, b => b.show(name))
+    }
+
+    fn reset_stats(&mut self) {
+        dispatch!(self, b => b.reset_stats())
+    }
+
+    fn stats(&self) -> Option<CommandStats> {
+        dispatch!(self, b => b.stats())
+    }
+
+    async fn serve(&self, opts: &ServeOpts) -> anyhow::Result<()> {
+        dispatch!(self, b => b.serve(opts).await)
+    }
+
+    async fn snapshot(&self) -> anyhow::Result<Self> {
+        Ok(match self {
+            AnyBackend::DataFusion(b) => AnyBackend::DataFusion(b.snapshot().await?),
+            AnyBackend::Polars(b) => AnyBackend::Polars(Box::new(b.snapshot().await?)),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        dispatch!(self, b => b.name())
+    }
+
+    fn catalog(&self) -> Vec<(String, DatasetSource)> {
+        dispatch!(self, b => b.catalog())
+    }
+
+    async fn use_backend(&mut self, name: &str) -> anyhow::Result<Vec<String>> {
+        let mut backend = create_backend(name)?;
+        let mut errors = vec![];
+        for (name, value) in self.changed_settings() {
+            match backend.set(&name, &value).await {
+                Ok(None) => {}
+                Ok(Some(warning)) => errors.push(format!("set {} {}: {}", name, value, warning)),
+                Err(e) => errors.push(format!("set {} {}: {}", name, value, e)),
+            }
+        }
+        errors.extend(replay(&mut backend, self.catalog()).await);
+        *self = backend;
+        Ok(errors)
+    }
+
+    // the other backends start from a copy of the catalog, so cached datasets are computed again
+    async fn verify(&self, sql: &str) -> anyhow::Result<VerifyReport> {
+        let current = self.name();
+        let mut results = vec![(current.to_string(), vec![], self.collect(sql).await)];
+        for name in backend_names().into_iter().filter(|name| *name != current) {
+            let mut errors = vec![];
+            let result = async {
+                let mut backend = create_backend(name)?;
+                // a dataset missing on the backend may fail the query, the report tells why
+                errors = replay(&mut backend, self.catalog()).await;
+                backend.collect(sql).await
+            }
+            .await;
+            results.push((name.to_string(), errors, result));
+        }
+        VerifyReport::try_new(results)
+    }
+}
+
+impl ReplDisplay for String {
+    async fn display(self, _output: OutputOptions) -> anyhow::Result<String> {
+        Ok(self)
+    }
+}
+
+// register the datasets of another backend, files first, then views and caches until no more
+// can be registered as they may depend on each other, returning the errors of the rest
+async fn replay(backend: &mut AnyBackend, catalog: Vec<(String, DatasetSource)>) -> Vec<String> {
+    let mut pending = catalog;
+    pending.sort_by_key(|(_, source)| !matches!(source, DatasetSource::File(_)));
+    loop {
+        let count = pending.len();
+        let mut failed = vec![];
+        let mut errors = vec![];
+        for (name, source) in pending {
+            if let Err(e) = register(backend, &name, &source).await {
+                errors.push(format!("{}: {}", name, e));
+                failed.push((name, source));
+            }
+        }
+        if failed.is_empty() || failed.len() == count {
+            return errors;
+        }
+        pending = failed;
+    }
+}
+
+async fn register(
+    backend: &mut AnyBackend,
+    name: &str,
+    source: &DatasetSource,
+) -> anyhow::Result<()> {
+    match source {
+        DatasetSource::File(conn) => {
+            let opts = ConnectOpts::new(conn.clone(), None, name.to_string());
+            backend.connect(&opts).await
+        }
+        DatasetSource::View(sql) => backend.create_view(name, sql, false).await,
+        DatasetSource::Memory { sql, .. } => backend.cache(name, sql, false).await.map(|_| ()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::OutputFormat;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_use_backend() -> anyhow::Result<()> {
+        let mut backend = create_backend("datafusion")?;
+        backend.set("memory_limit", "1GB").await?;
+        backend.set("target_partitions", "3").await?;
+        backend.set("format", "csv").await?;
+
+        // polars warns of the settings it ignores, but keeps them for the next backend
+        let errors = backend.use_backend("polars").await?;
+        assert_eq!(
+            errors,
+            [
+                "set memory_limit 1GB: the polars backend ignores memory_limit",
+                "set target_partitions 3: the polars backend ignores target_partitions",
+            ]
+        );
+        assert_eq!(backend.output().format, OutputFormat::Csv);
+
+        let errors = backend.use_backend("datafusion").await?;
+        assert!(errors.is_empty(), "{:?}", errors);
+        let partitions = backend
+            .show("target_partitions")
+            .await?
+            .display(Default::default())
+            .await?;
+        assert!(
+            partitions.contains("target_partitions,3,"),
+            "{}",
+            partitions
+        );
+        assert_eq!(
+            backend.changed_settings(),
+            vec![
+                ("memory_limit".to_string(), "1GB".to_string()),
+                ("target_partitions".to_string(), "3".to_string()),
+                ("format".to_string(), "csv".to_string()),
+            ]
+        );
+        Ok(())
+    }
+}
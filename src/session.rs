@@ -0,0 +1,185 @@
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
+use datafusion::{execution::SendableRecordBatchStream, prelude::SessionContext};
+
+use crate::{
+    backend::{CheckReport, DataFusionBackend, DatasetInfo, Rule},
+    cli::{ConnectOpts, ConvertOpts, DatasetConn, ParquetCodec},
+    Backend,
+};
+
+/// A taotie session to embed in other tools, without the REPL or text output.
+///
+/// ```no_run
+/// # async fn run() -> anyhow::Result<()> {
+/// use futures::TryStreamExt;
+///
+/// let mut taotie = taotie::Taotie::new();
+/// taotie.connect("sales", "data/sales.parquet").await?;
+/// let stream = taotie.sql("SELECT region, sum(amount) FROM sales GROUP BY region").await?;
+/// let batches: Vec<_> = stream.try_collect().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Taotie {
+    backend: DataFusionBackend,
+}
+
+/// How [`Taotie::convert`] writes its output, as the options of the `convert` command.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// Columns to partition the output by.
+    pub partition_by: Vec<String>,
+    /// Columns to sort the output by, append `:desc` for descending order.
+    pub sort_by: Vec<String>,
+    /// The max number of rows in a parquet row group.
+    pub row_group_size: Option<usize>,
+    /// The compression codec of the parquet output.
+    pub codec: Option<ParquetCodec>,
+}
+
+impl Taotie {
+    pub fn new() -> Self {
+        Self {
+            backend: DataFusionBackend::new(),
+        }
+    }
+
+    /// Register a dataset, where `conn` is a connection string as accepted by the `connect`
+    /// command, e.g. `data/sales.csv.gz` or `data/events.parquet`.
+    pub async fn connect(&mut self, name: &str, conn: &str) -> anyhow::Result<()> {
+        let conn: DatasetConn = conn.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+        let opts = ConnectOpts::new(conn, None, name.to_string());
+        self.backend.connect(&opts).await
+    }
+
+    /// The registered datasets, counting the rows of those without metadata when `exact`.
+    pub async fn datasets(&self, exact: bool) -> anyhow::Result<Vec<DatasetInfo>> {
+        self.backend.dataset_infos(exact).await
+    }
+
+    /// The schema of a registered dataset or a local file.
+    pub async fn schema(&self, name: &str) -> anyhow::Result<SchemaRef> {
+        self.backend.schema(name).await
+    }
+
+    /// Run a SQL query and stream its results.
+    pub async fn sql(&self, sql: &str) -> anyhow::Result<SendableRecordBatchStream> {
+        let df = self.context().sql(sql).await?;
+        Ok(df.execute_stream().await?)
+    }
+
+    /// The summary statistics of each column of a dataset, one row per statistic.
+    pub async fn describe(&self, name: &str) -> anyhow::Result<Vec<RecordBatch>> {
+        let stats = self.backend.describe_stats(name).await?;
+        Ok(stats.collect().await?)
+    }
+
+    /// The first `size` rows of a registered dataset or a local file.
+    pub async fn head(&self, name: &str, size: usize) -> anyhow::Result<Vec<RecordBatch>> {
+        let df = self.backend.dataframe(name).await?;
+        Ok(df.limit(0, Some(size))?.collect().await?)
+    }
+
+    /// Check a dataset against data quality rules, e.g. loaded with [`Rule::load`].
+    pub async fn check(&self, name: &str, rules: &[Rule]) -> anyhow::Result<CheckReport> {
+        self.backend.check(name, rules).await
+    }
+
+    /// Convert a dataset or a local file to `dst`, a file whose extension gives the format and
+    /// compression, returning the number of rows written.
+    pub async fn convert(
+        &self,
+        src: &str,
+        dst: &str,
+        options: ConvertOptions,
+    ) -> anyhow::Result<u64> {
+        let dst: DatasetConn = dst.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+        let opts = ConvertOpts::new(
+            src.to_string(),
+            dst,
+            options.partition_by,
+            options.sort_by,
+            options.row_group_size,
+            options.codec,
+        );
+        opts.verify()?;
+        self.backend.convert(&opts).await
+    }
+
+    /// Set an option, as with the `set` command, e.g. `memory_limit` or `target_partitions`.
+    pub async fn set(&mut self, name: &str, value: &str) -> anyhow::Result<()> {
+        self.backend.set(name, value).await?;
+        Ok(())
+    }
+
+    /// The underlying DataFusion session, to run anything not covered above.
+    pub fn context(&self) -> &SessionContext {
+        &self.backend
+    }
+}
+
+impl Default for Taotie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int64Type;
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::test_utils::TestDir;
+
+    #[tokio::test]
+    async fn test_session() -> anyhow::Result<()> {
+        let dir = TestDir::new();
+        let csv = dir.write("sales.csv", "region,amount\neast,10\nwest,20\neast,5\n");
+
+        let mut taotie = Taotie::new();
+        taotie.connect("sales", &csv).await?;
+        assert!(taotie
+            .connect("pg", "postgres://localhost/db")
+            .await
+            .is_err());
+
+        let stream = taotie
+            .sql("SELECT sum(amount) FROM sales WHERE region = 'east'")
+            .await?;
+        let batches: Vec<RecordBatch> = stream.try_collect().await?;
+        assert_eq!(
+            batches[0].column(0).as_primitive::<Int64Type>().value(0),
+            15
+        );
+
+        let stats = taotie.describe("sales").await?;
+        let columns: Vec<_> = stats[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(columns, ["describe", "region", "amount"]);
+        assert!(stats.iter().map(|b| b.num_rows()).sum::<usize>() > 0);
+
+        let head = taotie.head("sales", 2).await?;
+        assert_eq!(head.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+
+        let dst = dir.path("sales.parquet");
+        let options = ConvertOptions {
+            sort_by: vec!["amount:desc".to_string()],
+            ..Default::default()
+        };
+        let rows = taotie.convert("sales", &dst, options).await?;
+        assert_eq!(rows, 3);
+        let options = ConvertOptions {
+            codec: Some(ParquetCodec::Zstd),
+            ..Default::default()
+        };
+        let dst = dir.path("sales.json");
+        assert!(taotie.convert("sales", &dst, options).await.is_err());
+        Ok(())
+    }
+}
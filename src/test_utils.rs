@@ -0,0 +1,50 @@
+use std::{fs::File, path::Path};
+
+use arrow::array::RecordBatch;
+use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+use tempfile::TempDir;
+
+use crate::cli::{ConnectOpts, DatasetConn};
+
+// a directory of test files, removed when the test ends, even when an assertion fails
+pub(crate) struct TestDir(TempDir);
+
+impl TestDir {
+    pub(crate) fn new() -> Self {
+        Self(tempfile::tempdir().expect("failed to create a temp dir"))
+    }
+
+    pub(crate) fn path(&self, name: &str) -> String {
+        self.0.path().join(name).to_string_lossy().to_string()
+    }
+
+    pub(crate) fn write(&self, name: &str, content: &str) -> String {
+        let path = self.path(name);
+        std::fs::write(&path, content).expect("failed to write a test file");
+        path
+    }
+
+    // a parquet file of the batch, split in row groups of at most `row_group_size` rows
+    pub(crate) fn write_parquet(
+        &self,
+        name: &str,
+        batch: &RecordBatch,
+        row_group_size: usize,
+    ) -> anyhow::Result<String> {
+        let path = self.path(name);
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(row_group_size)
+            .build();
+        let mut writer = ArrowWriter::try_new(File::create(&path)?, batch.schema(), Some(props))?;
+        writer.write(batch)?;
+        writer.close()?;
+        Ok(path)
+    }
+}
+
+// the options of `connect <path> --name <name>`
+pub(crate) fn connect_opts(name: &str, path: impl AsRef<Path>) -> ConnectOpts {
+    let path = path.as_ref().to_string_lossy();
+    let conn: DatasetConn = path.parse().expect("invalid test file");
+    ConnectOpts::new(conn, None, name.to_string())
+}
@@ -0,0 +1,134 @@
+const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+const BARS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+pub(crate) fn human_bytes(size: u64) -> String {
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+// the inverse of `human_bytes`, e.g. `4GB`, `512MiB` or `1.5g`, units are powers of 1024
+pub(crate) fn parse_bytes(s: &str) -> anyhow::Result<usize> {
+    let s = s.trim();
+    let pos = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(pos);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid size: {}", s))?;
+    let unit = unit.trim().to_ascii_uppercase();
+    let unit = unit.trim_end_matches('B').trim_end_matches('I');
+    let power = match unit {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => anyhow::bail!("invalid size unit: {}", s),
+    };
+    Ok((number * 1024f64.powi(power)) as usize)
+}
+
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub(crate) fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+// a sort key of `--sort-by` or `--order-by`: `name`, `name:asc` or `name:desc`, true if descending
+pub(crate) fn sort_key(key: &str) -> (&str, bool) {
+    match key.rsplit_once(':') {
+        Some((name, "desc")) => (name, true),
+        Some((name, "asc")) => (name, false),
+        _ => (key, false),
+    }
+}
+
+// remember a setting to apply it again on another backend, replacing an earlier value
+pub(crate) fn track_setting(changed: &mut Vec<(String, String)>, name: &str, value: &str) {
+    changed.retain(|(n, _)| n != name);
+    changed.push((name.to_string(), value.to_string()));
+}
+
+// a horizontal bar of at most `width` characters, in steps of 1/8 character
+pub(crate) fn bar(value: u64, max: u64, width: usize) -> String {
+    if max == 0 || value == 0 {
+        return String::new();
+    }
+    let eighths = (value as f64 / max as f64 * (width * 8) as f64).round() as usize;
+    let eighths = eighths.max(1);
+    let mut bar = BARS[7].to_string().repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(BARS[eighths % 8 - 1]);
+    }
+    bar
+}
+
+pub(crate) fn sparkline(values: &[u64]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&v| match v {
+            0 => ' ',
+            v => SPARKS[((v * 7) as f64 / max as f64).round() as usize],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(human_bytes(0), "0 B");
+        assert_eq!(human_bytes(1023), "1023 B");
+        assert_eq!(human_bytes(1024), "1.0 KiB");
+        assert_eq!(human_bytes(1536 * 1024), "1.5 MiB");
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(parse_bytes("100").unwrap(), 100);
+        assert_eq!(parse_bytes("4GB").unwrap(), 4 << 30);
+        assert_eq!(parse_bytes("512MiB").unwrap(), 512 << 20);
+        assert_eq!(parse_bytes("1.5k").unwrap(), 1536);
+        assert!(parse_bytes("4XB").is_err());
+        assert!(parse_bytes("GB").is_err());
+    }
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("Name"), "\"Name\"");
+        assert_eq!(quote_ident("a\"b"), "\"a\"\"b\"");
+        assert_eq!(quote_literal("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn test_sort_key() {
+        assert_eq!(sort_key("amount"), ("amount", false));
+        assert_eq!(sort_key("amount:desc"), ("amount", true));
+        assert_eq!(sort_key("amount:asc"), ("amount", false));
+        assert_eq!(sort_key("a:b"), ("a:b", false));
+    }
+
+    #[test]
+    fn test_bar_and_sparkline() {
+        assert_eq!(bar(0, 10, 4), "");
+        assert_eq!(bar(10, 10, 4), "████");
+        assert_eq!(bar(5, 10, 3), "█▌");
+        assert_eq!(sparkline(&[0, 2, 8, 14]), " ▂▅█");
+    }
+}